@@ -1,88 +1,397 @@
-pub mod zk_auth {
-    include!("./zkp_auth.rs");
-}
 // get the user name to add maps in the server
-use std::io::{stdin, Read};
-// coming from the generated rs file using proto
-use num_bigint::BigUint;
-use zk_auth::{
-    auth_client::AuthClient, AuthenticationAnswerRequest, AuthenticationChallengeRequest,
-    RegisterRequest,
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+use ChaumPedersen::{
+    sdk::{ClientError, Credential, Session},
+    GroupId, GroupParams, ZkAuthClient,
 };
-use ChaumPedersen::ZKP;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "client",
+    about = "Log in to the Chaum-Pedersen auth server without sending your password"
+)]
+struct Cli {
+    /// server to talk to, https:// needs --tls-ca
+    #[arg(long, env = "ZKP_SERVER", default_value = "http://127.0.0.1:50051")]
+    server: String,
+    /// only trust a server certificate signed by this CA (PEM)
+    #[arg(long, env = "ZKP_TLS_CA")]
+    tls_ca: Option<PathBuf>,
+    /// read passwords from this file descriptor, one per line, instead of prompting
+    /// (e.g. `--password-fd 3 3<secret.txt` or `--password-fd 0` for stdin)
+    #[arg(long)]
+    password_fd: Option<u32>,
+    /// where login keeps the session for the other commands [default: ~/.zkp_session]
+    #[arg(long, env = "ZKP_SESSION_FILE")]
+    session_file: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// create an account
+    Register {
+        user: String,
+        /// modp2048, ristretto255 (small messages), p256 or secp256k1
+        #[arg(long, default_value_t = GroupId::Ristretto255)]
+        group: GroupId,
+    },
+    /// prove you know the password and keep the session
+    Login {
+        user: String,
+        /// one Login call (Fiat-Shamir) instead of challenge + answer
+        #[arg(long)]
+        fiat_shamir: bool,
+    },
+    /// who the stored session belongs to and how long it lasts
+    Whoami,
+    /// end the stored session
+    Logout,
+    /// change the password and log out everywhere
+    Rotate {
+        /// defaults to the logged in user
+        user: Option<String>,
+        /// group for the new verifier
+        #[arg(long, default_value_t = GroupId::Ristretto255)]
+        group: GroupId,
+    },
+    /// remove the account for good
+    Delete {
+        /// defaults to the logged in user
+        user: Option<String>,
+    },
+}
+
+/// what login leaves behind for whoami, logout, rotate and delete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredSession {
+    server: String,
+    #[serde(flatten)]
+    session: Session,
+}
+
+impl StoredSession {
+    fn load(path: &Path) -> Result<Option<StoredSession>, String> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// owner read/write only, the session_id is as good as the password until it expires
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(self).expect("plain structs always serialize");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let write = |mut file: fs::File| {
+            // mode only counts when the file is created, tighten one that was already there
+            // before the session goes in
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            }
+            file.write_all(&json)
+        };
+        options
+            .open(path)
+            .and_then(write)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn remove(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// hidden prompt on the terminal, or lines from --password-fd for scripts
+enum Passwords {
+    Prompt,
+    Fd(Box<dyn BufRead>),
+}
+
+impl Passwords {
+    fn open(fd: Option<u32>) -> Result<Passwords, String> {
+        match fd {
+            None => Ok(Passwords::Prompt),
+            // /dev/fd/N is a new handle on whatever the caller passed as fd N
+            Some(fd) => fs::File::open(format!("/dev/fd/{}", fd))
+                .map(|file| Passwords::Fd(Box::new(BufReader::new(file))))
+                .map_err(|e| format!("--password-fd {}: {}", fd, e)),
+        }
+    }
+
+    fn read(&mut self, prompt: &str) -> Result<String, String> {
+        let password = match self {
+            Passwords::Prompt => rpassword::prompt_password(prompt).map_err(|e| e.to_string())?,
+            Passwords::Fd(reader) => {
+                let mut line = String::new();
+                if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+                    return Err("--password-fd ran out of passwords".to_string());
+                }
+                line.trim_end_matches(['\r', '\n']).to_string()
+            }
+        };
+        if password.is_empty() {
+            return Err("empty password".to_string());
+        }
+        Ok(password)
+    }
+
+    /// a new password, typed twice when someone is at the keyboard
+    fn read_new(&mut self, prompt: &str) -> Result<String, String> {
+        let password = self.read(prompt)?;
+        if let Passwords::Prompt = self {
+            if self.read("Repeat it: ")? != password {
+                return Err("passwords do not match".to_string());
+            }
+        }
+        Ok(password)
+    }
+}
+
 // async main
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    // same parameter checks as the server, before we derive anything from them
+    GroupParams::rfc5114()
+        .validate()
+        .map_err(|e| format!("bad {} parameters: {}", GroupId::ModP2048, e))?;
+
+    let session_file = match cli.session_file {
+        Some(path) => path,
+        None => std::env::var_os("HOME")
+            .map(|home| Path::new(&home).join(".zkp_session"))
+            .ok_or("no HOME, pass --session-file")?,
+    };
+    let mut passwords = Passwords::open(cli.password_fd)?;
+
     // connet to the server
-    let mut client = AuthClient::connect("http://127.0.0.1:50051")
+    let mut endpoint = Channel::from_shared(cli.server.clone()).map_err(|e| e.to_string())?;
+    if let Some(path) = &cli.tls_ca {
+        let ca = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().ca_certificate(Certificate::from_pem(ca)))
+            .map_err(|e| e.to_string())?;
+    }
+    let channel = endpoint
+        .connect()
         .await
-        .expect("Connection failed");
-    println!("✅ Running the client");
-    println!("Username: ");
-    let mut username = String::new();
-    stdin()
-        .read_line(&mut username)
-        .expect("user name is not specified");
-
-    println!("Password: ");
-    let mut secret = String::new();
-    stdin()
-        .read_line(&mut secret)
-        .expect("password is not specified");
-
-    // get constants
-    let (a, b, p, q) = ZKP::get_constants();
-    // generate y1, y2
-    let request = RegisterRequest {
-        user: username.clone().trim().to_string(),
-        y1: ZKP::mod_exp(&a, &BigUint::from_bytes_be(&secret.trim().as_bytes()), &p).to_bytes_be(),
-        y2: ZKP::mod_exp(&b, &BigUint::from_bytes_be(&secret.trim().as_bytes()), &p).to_bytes_be(),
-    };
-    let response = client.register(request).await.expect("Register failed");
-    // println!("Response from server: {:?}", response);
-
-    // lets generate r1 and r2
-    let k = ZKP::gen_rand(&q);
-    let r1 = ZKP::mod_exp(&a, &k, &p); //&BigUint::from_bytes_be(secret.trim().as_bytes())
-    let r2 = ZKP::mod_exp(&b, &k, &p);
-
-    let request = AuthenticationChallengeRequest {
-        user: username.clone().trim().to_string(),
-        r1: r1.to_bytes_be(),
-        r2: r2.to_bytes_be(),
-    };
-    // println!("Request from client: {:?}", request);
-    let challenge_auth_id = client
-        .create_authentication_challenge(request)
+        .map_err(|e| format!("could not connect to {}: {}", cli.server, e))?;
+    let mut client = ZkAuthClient::new(channel);
+
+    match cli.command {
+        Command::Register { user, group } => {
+            let password = passwords.read_new("Password: ")?;
+            let mut client = client.with_group(group);
+            match client.register(&user, &password).await {
+                Err(ClientError::AlreadyExists(_)) => {
+                    Err(format!("{} is already registered", user))
+                }
+                result => result.map_err(failed("Register")),
+            }?;
+            println!("✅ Registered {:?} with {}", user, group);
+        }
+        Command::Login { user, fiat_shamir } => {
+            let password = passwords.read("Password: ")?;
+            let session = if fiat_shamir {
+                client.login_fiat_shamir(&user, &password).await
+            } else {
+                client.login(&user, &password).await
+            }
+            .map_err(failed("Login"))?;
+            let stored = StoredSession {
+                server: cli.server.clone(),
+                session,
+            };
+            stored.save(&session_file)?;
+            show_session(&mut client, &stored.session).await?;
+        }
+        Command::Whoami => {
+            let stored = stored_session(&session_file, &cli.server)?.ok_or("not logged in")?;
+            show_session(&mut client, &stored.session).await?;
+        }
+        Command::Logout => {
+            let stored = stored_session(&session_file, &cli.server)?.ok_or("not logged in")?;
+            client
+                .logout(&stored.session)
+                .await
+                .map_err(failed("Logout"))?;
+            StoredSession::remove(&session_file);
+            println!("✅ Logged out {:?}", stored.session.user);
+        }
+        Command::Rotate { user, group } => {
+            let stored = stored_session(&session_file, &cli.server)?;
+            let user = user_or_session(user, &stored)?;
+            let current = current_password(&mut passwords, &stored, &user)?;
+            let new = passwords.read_new("New password: ")?;
+            let credential = credential(&current, &stored)?;
+            client
+                .with_group(group)
+                .rotate_credentials(&user, credential, &new)
+                .await
+                .map_err(failed("RotateCredentials"))?;
+            // every session is gone, ours too
+            StoredSession::remove(&session_file);
+            println!("✅ New password for {:?}, log in again", user);
+        }
+        Command::Delete { user } => {
+            let stored = stored_session(&session_file, &cli.server)?;
+            let user = user_or_session(user, &stored)?;
+            let current = current_password(&mut passwords, &stored, &user)?;
+            let credential = credential(&current, &stored)?;
+            client
+                .delete_account(&user, credential)
+                .await
+                .map_err(failed("DeleteAccount"))?;
+            StoredSession::remove(&session_file);
+            println!("✅ Deleted {:?}", user);
+        }
+    }
+    Ok(())
+}
+
+fn failed(what: &str) -> impl Fn(ClientError) -> String + '_ {
+    move |e| format!("{} failed: {}", what, e)
+}
+
+// the session file, if it was made against this server
+fn stored_session(path: &Path, server: &str) -> Result<Option<StoredSession>, String> {
+    Ok(StoredSession::load(path)?.filter(|stored| stored.server == server))
+}
+
+fn user_or_session(user: Option<String>, stored: &Option<StoredSession>) -> Result<String, String> {
+    user.or_else(|| stored.as_ref().map(|stored| stored.session.user.clone()))
+        .ok_or_else(|| "not logged in, name the user".to_string())
+}
+
+// a session of the same user authorizes on its own, otherwise we need the password
+fn current_password(
+    passwords: &mut Passwords,
+    stored: &Option<StoredSession>,
+    user: &str,
+) -> Result<Option<String>, String> {
+    match stored {
+        Some(stored) if stored.session.user == user => Ok(None),
+        _ => passwords.read("Current password: ").map(Some),
+    }
+}
+
+fn credential<'a>(
+    current: &'a Option<String>,
+    stored: &'a Option<StoredSession>,
+) -> Result<Credential<'a>, String> {
+    match (current, stored) {
+        (Some(password), _) => Ok(Credential::Password(password)),
+        (None, Some(stored)) => Ok(Credential::Session(&stored.session)),
+        (None, None) => Err("not logged in".to_string()),
+    }
+}
+
+// ask the server who the session belongs to, proves it was actually stored,
+// and check the token offline the way a downstream service would
+async fn show_session(client: &mut ZkAuthClient, session: &Session) -> Result<(), String> {
+    let claims = client
+        .verify_token(&session.token)
         .await
-        .expect("Couldnt get a challenge from server");
-    // println!("Challenge response: {:?}", challenge_auth_id);
-
-    let auth_id = challenge_auth_id.get_ref();
-    // println!("Challenge response: {:?}", auth_id.);
-    // let response = ZKP::proof(
-    //     &k,
-    //     auth_id.c,
-    //     &BigUint::from_bytes_be(&secret.trim().as_bytes()),
-    // );
-    /// now its time to generate the proof as a client that shows we know the password without sharing it
-    /// generate response using challenge which is s = k - c.x
-    ///
-    let zk = ZKP::init(&a, &b, &p, &q);
-    let proof = zk.proof(
-        &k,
-        &BigUint::from_bytes_be(&auth_id.c),
-        &BigUint::from_bytes_be(&secret.trim().as_bytes()),
+        .map_err(|e| e.to_string())?;
+    println!(
+        "✅ Token for {:?}, methods {:?}, expires in {}s",
+        claims.sub,
+        claims.amr,
+        session.expires_in()
     );
-
-    let auth_req = AuthenticationAnswerRequest {
-        auth_id: auth_id.auth_id.to_string(),
-        s: proof.to_bytes_be(),
-    };
-    let session_id = client
-        .verify_authentication(auth_req)
+    let validated = client
+        .validate(session)
         .await
-        .expect("Couldnt get a session id from server");
+        .map_err(failed("ValidateSession"))?;
+    println!(
+        "✅ Logged in as {:?}, session expires in {}s",
+        validated.user,
+        validated.expires_in()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_session_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session");
+        assert_eq!(StoredSession::load(&path).unwrap(), None);
+
+        let session = StoredSession {
+            server: "http://127.0.0.1:50051".to_string(),
+            session: Session {
+                user: "alice".to_string(),
+                session_id: "abc".to_string(),
+                token: "a.b.c".to_string(),
+                expires_at: 1_700_000_000,
+            },
+        };
+        session.save(&path).unwrap();
+        assert_eq!(StoredSession::load(&path).unwrap(), Some(session.clone()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            // a file that was already there readable by everyone gets tightened too
+            let loose = dir.path().join("loose");
+            fs::write(&loose, b"{}").unwrap();
+            fs::set_permissions(&loose, fs::Permissions::from_mode(0o644)).unwrap();
+            session.save(&loose).unwrap();
+            assert_eq!(mode(&loose), 0o600);
+            assert_eq!(StoredSession::load(&loose).unwrap(), Some(session.clone()));
+        }
+        // a session for another server is not ours to use
+        assert_eq!(
+            stored_session(&path, "http://elsewhere:50051").unwrap(),
+            None
+        );
+        StoredSession::remove(&path);
+        assert_eq!(StoredSession::load(&path).unwrap(), None);
+    }
 
-    println!("session id: {:?}", session_id.get_ref().session_id);
+    #[test]
+    fn test_password_fd() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords");
+        fs::write(&path, "hunter2\ncorrect horse\r\n\n").unwrap();
+        let file = fs::File::open(&path).unwrap();
+        let mut passwords = Passwords::Fd(Box::new(BufReader::new(file)));
+        assert_eq!(passwords.read("").unwrap(), "hunter2");
+        // no confirmation from a script
+        assert_eq!(passwords.read_new("").unwrap(), "correct horse");
+        assert!(passwords.read("").is_err());
+        assert!(passwords.read("").is_err());
+    }
 }
@@ -0,0 +1,469 @@
+use std::{
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use ChaumPedersen::GroupId;
+
+use crate::{
+    ratelimit::{Limits, Rate},
+    DEFAULT_CHALLENGE_TTL, DEFAULT_SESSION_TTL,
+};
+
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// where users, challenges and sessions live
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// gone on restart
+    #[default]
+    Memory,
+    /// SQLite file (cargo feature sqlite)
+    Sqlite,
+    /// redb file (cargo feature kv)
+    Kv,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Backend::Memory => "memory",
+            Backend::Sqlite => "sqlite",
+            Backend::Kv => "kv",
+        })
+    }
+}
+
+/// how log lines are written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// for people
+    #[default]
+    Text,
+    /// one JSON object per line, for log pipelines
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        })
+    }
+}
+
+/// command line, every flag can also come from its ZKP_* env var. what is set here wins
+/// over the config file, which wins over the defaults
+#[derive(Default, Parser)]
+#[command(name = "server", about = "Chaum-Pedersen authentication server")]
+pub struct Args {
+    /// TOML file with any of the settings below
+    #[arg(long, env = "ZKP_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// address to listen on [default: 127.0.0.1:50051]
+    #[arg(long, env = "ZKP_LISTEN")]
+    pub listen: Option<SocketAddr>,
+    /// groups users may register with, comma separated [default: all built in]
+    #[arg(long, env = "ZKP_GROUPS", value_delimiter = ',')]
+    pub groups: Option<Vec<GroupId>>,
+    /// seconds an auth_id can be answered [default: 60]
+    #[arg(long, env = "ZKP_CHALLENGE_TTL")]
+    pub challenge_ttl: Option<u64>,
+    /// seconds a session lasts before it has to be refreshed [default: 3600]
+    #[arg(long, env = "ZKP_SESSION_TTL")]
+    pub session_ttl: Option<u64>,
+    /// storage backend [default: memory]
+    #[arg(long, env = "ZKP_STORAGE")]
+    pub storage: Option<Backend>,
+    /// file for the sqlite and kv backends
+    #[arg(long, env = "ZKP_STORAGE_PATH")]
+    pub storage_path: Option<PathBuf>,
+    /// shorthand for --storage sqlite --storage-path <DB>
+    #[arg(long, conflicts_with_all = ["kv", "storage", "storage_path"])]
+    pub db: Option<PathBuf>,
+    /// shorthand for --storage kv --storage-path <KV>
+    #[arg(long, conflicts_with_all = ["storage", "storage_path"])]
+    pub kv: Option<PathBuf>,
+    /// Ed25519 key for session tokens (PKCS#8 PEM), created on first start [default: token_key.pem]
+    #[arg(long, env = "ZKP_TOKEN_KEY_PATH")]
+    pub token_key_path: Option<PathBuf>,
+    /// the token key PEM itself instead of a file, for secret stores that hand out env vars
+    #[arg(long, env = "ZKP_TOKEN_KEY", hide_env_values = true)]
+    pub token_key: Option<String>,
+    /// server certificate (PEM), serves over TLS together with --tls-key
+    #[arg(long, env = "ZKP_TLS_CERT")]
+    pub tls_cert: Option<PathBuf>,
+    /// server private key (PEM)
+    #[arg(long, env = "ZKP_TLS_KEY")]
+    pub tls_key: Option<PathBuf>,
+    /// CA for client certificates (PEM), enables mTLS and the Admin service
+    #[arg(long, env = "ZKP_CLIENT_CA")]
+    pub client_ca: Option<PathBuf>,
+    /// attempts (GetSalt, challenges, answers, proofs) one user can make at once [default: 10]
+    #[arg(long, env = "ZKP_USER_BURST")]
+    pub user_burst: Option<u32>,
+    /// attempts a user gets back per minute [default: 30]
+    #[arg(long, env = "ZKP_USER_PER_MINUTE")]
+    pub user_per_minute: Option<u32>,
+    /// attempts (GetSalt, challenges, answers, proofs) one client address can make at once
+    /// [default: 50]
+    #[arg(long, env = "ZKP_PEER_BURST")]
+    pub peer_burst: Option<u32>,
+    /// attempts an address gets back per minute [default: 120]
+    #[arg(long, env = "ZKP_PEER_PER_MINUTE")]
+    pub peer_per_minute: Option<u32>,
+    /// failed proofs in a row before a user or address is locked out [default: 5]
+    #[arg(long, env = "ZKP_LOCKOUT_AFTER")]
+    pub lockout_after: Option<u32>,
+    /// seconds of the first lockout, doubled with every further failure [default: 30]
+    #[arg(long, env = "ZKP_LOCKOUT_BASE")]
+    pub lockout_base: Option<u64>,
+    /// longest lockout in seconds [default: 900]
+    #[arg(long, env = "ZKP_LOCKOUT_MAX")]
+    pub lockout_max: Option<u64>,
+    /// off, error, warn, info, debug or trace [default: info]. RUST_LOG, when set, replaces
+    /// it with its own directives (e.g. RUST_LOG=info,h2=debug)
+    #[arg(long, env = "ZKP_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// text or json [default: text]
+    #[arg(long, env = "ZKP_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+}
+
+/// the config file, same names as the flags with storage, tls and rate_limit in their own tables
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    listen: Option<SocketAddr>,
+    groups: Option<Vec<String>>,
+    challenge_ttl: Option<u64>,
+    session_ttl: Option<u64>,
+    token_key_path: Option<PathBuf>,
+    token_key: Option<String>,
+    log_level: Option<String>,
+    log_format: Option<LogFormat>,
+    #[serde(default)]
+    storage: FileStorage,
+    #[serde(default)]
+    tls: FileTls,
+    #[serde(default)]
+    rate_limit: FileRateLimit,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileStorage {
+    backend: Option<Backend>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileTls {
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+    client_ca: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileRateLimit {
+    user_burst: Option<u32>,
+    user_per_minute: Option<u32>,
+    peer_burst: Option<u32>,
+    peer_per_minute: Option<u32>,
+    lockout_after: Option<u32>,
+    lockout_base: Option<u64>,
+    lockout_max: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tls {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub client_ca: Option<PathBuf>,
+}
+
+/// what the server runs with once flags, env, file and defaults are merged
+#[derive(Clone, PartialEq, Eq)]
+pub struct Config {
+    pub listen: SocketAddr,
+    pub groups: Vec<GroupId>,
+    pub challenge_ttl: Duration,
+    pub session_ttl: Duration,
+    pub storage: Backend,
+    /// set for every backend but memory
+    pub storage_path: Option<PathBuf>,
+    pub token_key_path: PathBuf,
+    /// inline PEM, takes precedence over token_key_path. secret, never printed
+    pub token_key: Option<String>,
+    pub tls: Option<Tls>,
+    pub rate_limit: Limits,
+    pub log_level: String,
+    pub log_format: LogFormat,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            listen: SocketAddr::from(([127, 0, 0, 1], 50051)),
+            groups: GroupId::ALL.to_vec(),
+            challenge_ttl: DEFAULT_CHALLENGE_TTL,
+            session_ttl: DEFAULT_SESSION_TTL,
+            storage: Backend::Memory,
+            storage_path: None,
+            token_key_path: PathBuf::from("token_key.pem"),
+            token_key: None,
+            tls: None,
+            rate_limit: Limits::default(),
+            log_level: "info".to_string(),
+            log_format: LogFormat::Text,
+        }
+    }
+}
+
+impl Config {
+    /// flags and env from the process, plus the file they point at
+    pub fn from_env() -> Result<Config, String> {
+        Self::load(Args::parse())
+    }
+
+    pub fn load(args: Args) -> Result<Config, String> {
+        let file = match &args.config {
+            Some(path) => read_file(path)?,
+            None => File::default(),
+        };
+        let defaults = Config::default();
+
+        let groups = match (args.groups, file.groups) {
+            (Some(groups), _) => groups,
+            (None, Some(names)) => names
+                .iter()
+                .map(|name| name.parse())
+                .collect::<Result<_, _>>()?,
+            (None, None) => defaults.groups,
+        };
+        if groups.is_empty() {
+            return Err("groups: at least one group has to be enabled".to_string());
+        }
+
+        let ttl = |name: &str, secs: Option<u64>, default: Duration| match secs {
+            Some(0) => Err(format!("{}: has to be at least a second", name)),
+            Some(secs) => Ok(Duration::from_secs(secs)),
+            None => Ok(default),
+        };
+        let challenge_ttl = ttl(
+            "challenge_ttl",
+            args.challenge_ttl.or(file.challenge_ttl),
+            defaults.challenge_ttl,
+        )?;
+        let session_ttl = ttl(
+            "session_ttl",
+            args.session_ttl.or(file.session_ttl),
+            defaults.session_ttl,
+        )?;
+
+        let (storage, storage_path) = match (args.db, args.kv) {
+            (Some(path), _) => (Backend::Sqlite, Some(path)),
+            (_, Some(path)) => (Backend::Kv, Some(path)),
+            (None, None) => (
+                args.storage
+                    .or(file.storage.backend)
+                    .unwrap_or(defaults.storage),
+                args.storage_path.or(file.storage.path),
+            ),
+        };
+        match (storage, &storage_path) {
+            (Backend::Memory, Some(_)) => {
+                return Err("storage path: the memory backend has no file".to_string())
+            }
+            (Backend::Sqlite | Backend::Kv, None) => {
+                return Err(format!("storage path: the {} backend needs one", storage))
+            }
+            _ => {}
+        }
+
+        let tls = match (
+            args.tls_cert.or(file.tls.cert),
+            args.tls_key.or(file.tls.key),
+            args.client_ca.or(file.tls.client_ca),
+        ) {
+            (Some(cert), Some(key), client_ca) => Some(Tls {
+                cert,
+                key,
+                client_ca,
+            }),
+            (None, None, None) => None,
+            _ => return Err("tls: cert and key go together, client_ca needs both".to_string()),
+        };
+
+        let limits = file.rate_limit;
+        let count = |name: &str, value: Option<u32>, default: u32| match value {
+            Some(0) => Err(format!("rate_limit.{}: has to be at least 1", name)),
+            value => Ok(value.unwrap_or(default)),
+        };
+        let default_limits = &defaults.rate_limit;
+        let user = Rate {
+            burst: count(
+                "user_burst",
+                args.user_burst.or(limits.user_burst),
+                default_limits.user.burst,
+            )?,
+            per_minute: count(
+                "user_per_minute",
+                args.user_per_minute.or(limits.user_per_minute),
+                default_limits.user.per_minute,
+            )?,
+        };
+        let peer = Rate {
+            burst: count(
+                "peer_burst",
+                args.peer_burst.or(limits.peer_burst),
+                default_limits.peer.burst,
+            )?,
+            per_minute: count(
+                "peer_per_minute",
+                args.peer_per_minute.or(limits.peer_per_minute),
+                default_limits.peer.per_minute,
+            )?,
+        };
+        let lockout_after = count(
+            "lockout_after",
+            args.lockout_after.or(limits.lockout_after),
+            default_limits.lockout_after,
+        )?;
+        let lockout_base = ttl(
+            "rate_limit.lockout_base",
+            args.lockout_base.or(limits.lockout_base),
+            default_limits.lockout_base,
+        )?;
+        let lockout_max = ttl(
+            "rate_limit.lockout_max",
+            args.lockout_max.or(limits.lockout_max),
+            default_limits.lockout_max,
+        )?;
+        if lockout_max < lockout_base {
+            return Err("rate_limit.lockout_max: has to be at least lockout_base".to_string());
+        }
+        let rate_limit = Limits {
+            user,
+            peer,
+            lockout_after,
+            lockout_base,
+            lockout_max,
+        };
+
+        let log_level = args
+            .log_level
+            .or(file.log_level)
+            .unwrap_or(defaults.log_level)
+            .to_lowercase();
+        if !LOG_LEVELS.contains(&log_level.as_str()) {
+            return Err(format!(
+                "log_level: {} is not one of {}",
+                log_level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+
+        Ok(Config {
+            listen: args.listen.or(file.listen).unwrap_or(defaults.listen),
+            groups,
+            challenge_ttl,
+            session_ttl,
+            storage,
+            storage_path,
+            token_key_path: args
+                .token_key_path
+                .or(file.token_key_path)
+                .unwrap_or(defaults.token_key_path),
+            token_key: args.token_key.or(file.token_key),
+            tls,
+            rate_limit,
+            log_level,
+            log_format: args
+                .log_format
+                .or(file.log_format)
+                .unwrap_or(defaults.log_format),
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<File, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// by hand so the inline token key can't end up in a log or a panic message
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("listen", &self.listen)
+            .field("groups", &self.groups)
+            .field("challenge_ttl", &self.challenge_ttl)
+            .field("session_ttl", &self.session_ttl)
+            .field("storage", &self.storage)
+            .field("storage_path", &self.storage_path)
+            .field("token_key_path", &self.token_key_path)
+            .field("token_key", &self.token_key.as_ref().map(|_| "<redacted>"))
+            .field("tls", &self.tls)
+            .field("rate_limit", &self.rate_limit)
+            .field("log_level", &self.log_level)
+            .field("log_format", &self.log_format)
+            .finish()
+    }
+}
+
+/// one `name = value` per line, safe to log: the inline token key is redacted
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<&str> = self.groups.iter().map(|group| group.as_str()).collect();
+        writeln!(f, "listen = {}", self.listen)?;
+        writeln!(f, "groups = {}", groups.join(", "))?;
+        writeln!(f, "challenge_ttl = {}s", self.challenge_ttl.as_secs())?;
+        writeln!(f, "session_ttl = {}s", self.session_ttl.as_secs())?;
+        match &self.storage_path {
+            Some(path) => writeln!(f, "storage = {} ({})", self.storage, path.display())?,
+            None => writeln!(f, "storage = {}", self.storage)?,
+        }
+        match &self.token_key {
+            Some(_) => writeln!(f, "token_key = <redacted>")?,
+            None => writeln!(f, "token_key_path = {}", self.token_key_path.display())?,
+        }
+        match &self.tls {
+            Some(tls) => {
+                writeln!(f, "tls.cert = {}", tls.cert.display())?;
+                writeln!(f, "tls.key = {}", tls.key.display())?;
+                match &tls.client_ca {
+                    Some(client_ca) => writeln!(f, "tls.client_ca = {}", client_ca.display())?,
+                    None => writeln!(f, "tls.client_ca = off")?,
+                }
+            }
+            None => writeln!(f, "tls = off")?,
+        }
+        let limits = &self.rate_limit;
+        for (name, rate) in [("user", limits.user), ("peer", limits.peer)] {
+            writeln!(
+                f,
+                "rate_limit.{} = {} at once, {}/min",
+                name, rate.burst, rate.per_minute
+            )?;
+        }
+        writeln!(
+            f,
+            "rate_limit.lockout = after {} failures, {}s doubling up to {}s",
+            limits.lockout_after,
+            limits.lockout_base.as_secs(),
+            limits.lockout_max.as_secs()
+        )?;
+        writeln!(f, "log_level = {}", self.log_level)?;
+        write!(f, "log_format = {}", self.log_format)
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fmt, str::FromStr};
+
+mod modp;
+mod ristretto;
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+mod sec1;
+
+pub use modp::{is_probable_prime, GroupParams, ModPGroup, ParamError};
+pub use ristretto::RistrettoGroup;
+#[cfg(feature = "p256")]
+pub use sec1::P256Group;
+#[cfg(any(feature = "p256", feature = "secp256k1"))]
+pub use sec1::Sec1Group;
+#[cfg(feature = "secp256k1")]
+pub use sec1::Secp256k1Group;
+
+/// errors we hit while decoding elements/scalars coming from the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupError {
+    InvalidElement(String),
+    InvalidScalar(String),
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupError::InvalidElement(reason) => write!(f, "invalid group element: {}", reason),
+            GroupError::InvalidScalar(reason) => write!(f, "invalid scalar: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+/// a cyclic group of prime order q together with the two generators a and b
+/// the protocol needs. we keep the multiplicative notation of the paper,
+/// so for elliptic curves `exp` is scalar multiplication and `mul` is point addition
+pub trait Group {
+    /// members of the group (y1, y2, r1, r2)
+    type Element: Clone + PartialEq + fmt::Debug;
+    /// exponents mod q (x, k, c, s)
+    type Scalar: Clone + PartialEq + fmt::Debug;
+
+    /// generators (a, b)
+    fn generators(&self) -> (&Self::Element, &Self::Element);
+    /// neutral element
+    fn identity(&self) -> Self::Element;
+    /// base^e
+    fn exp(&self, base: &Self::Element, e: &Self::Scalar) -> Self::Element;
+    /// x . y
+    fn mul(&self, x: &Self::Element, y: &Self::Element) -> Self::Element;
+
+    /// x * y mod q
+    fn scalar_mul(&self, x: &Self::Scalar, y: &Self::Scalar) -> Self::Scalar;
+    /// x - y mod q
+    fn scalar_sub(&self, x: &Self::Scalar, y: &Self::Scalar) -> Self::Scalar;
+    /// uniformly random scalar in [0, q)
+    fn random_scalar(&self) -> Self::Scalar;
+    /// reduce a 512-bit hash output mod q
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> Self::Scalar;
+
+    /// everything that pins down the group (modulus, curve, generators),
+    /// bound into Fiat-Shamir challenges so a proof can't move to another group
+    fn encode_params(&self) -> Vec<u8>;
+    fn encode_element(&self, element: &Self::Element) -> Vec<u8>;
+    fn decode_element(&self, bytes: &[u8]) -> Result<Self::Element, GroupError>;
+    fn encode_scalar(&self, scalar: &Self::Scalar) -> Vec<u8>;
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Self::Scalar, GroupError>;
+}
+
+/// groups the server knows about, sent as a string on the wire so clients can pick one at register
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GroupId {
+    /// RFC 5114 2048-bit MODP group, what old clients (no group field) use
+    #[default]
+    ModP2048,
+    Ristretto255,
+    #[cfg(feature = "p256")]
+    P256,
+    #[cfg(feature = "secp256k1")]
+    Secp256k1,
+}
+
+impl GroupId {
+    pub const ALL: &'static [GroupId] = &[
+        GroupId::ModP2048,
+        GroupId::Ristretto255,
+        #[cfg(feature = "p256")]
+        GroupId::P256,
+        #[cfg(feature = "secp256k1")]
+        GroupId::Secp256k1,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupId::ModP2048 => "modp2048",
+            GroupId::Ristretto255 => "ristretto255",
+            #[cfg(feature = "p256")]
+            GroupId::P256 => "p256",
+            #[cfg(feature = "secp256k1")]
+            GroupId::Secp256k1 => "secp256k1",
+        }
+    }
+}
+
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GroupId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // empty string is what proto3 gives us when the field is missing
+        if s.is_empty() {
+            return Ok(GroupId::default());
+        }
+        GroupId::ALL
+            .iter()
+            .find(|id| id.as_str() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown group {}", s))
+    }
+}
@@ -0,0 +1,302 @@
+use std::fmt;
+
+use num_bigint::{BigUint, RandBigInt};
+use sha2::{Digest, Sha256};
+
+use super::{Group, GroupError};
+
+/// Miller-Rabin rounds, error probability at most 4^-64 even for adversarial inputs
+const MILLER_RABIN_ROUNDS: usize = 64;
+
+/// what is wrong with a set of group parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    /// p fails the primality test
+    PNotPrime,
+    /// q fails the primality test
+    QNotPrime,
+    /// q does not divide p - 1, so there is no subgroup of order q
+    QDoesNotDividePMinusOne,
+    /// generator is 1 (or 0), it generates nothing
+    TrivialGenerator(&'static str),
+    /// generator is not reduced mod p
+    GeneratorOutOfRange(&'static str),
+    /// g^q != 1 mod p
+    GeneratorNotInSubgroup(&'static str),
+    /// a == b, y1 == y2 and the proof is plain Schnorr
+    GeneratorsEqual,
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::PNotPrime => write!(f, "p is not prime"),
+            ParamError::QNotPrime => write!(f, "q is not prime"),
+            ParamError::QDoesNotDividePMinusOne => write!(f, "q does not divide p - 1"),
+            ParamError::TrivialGenerator(g) => write!(f, "generator {} is trivial", g),
+            ParamError::GeneratorOutOfRange(g) => write!(f, "generator {} is not below p", g),
+            ParamError::GeneratorNotInSubgroup(g) => {
+                write!(f, "generator {} is not in the order q subgroup", g)
+            }
+            ParamError::GeneratorsEqual => write!(f, "generators a and b are equal"),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// raw a, b, p, q for a mod p group, run validate() before trusting them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupParams {
+    pub a: BigUint,
+    pub b: BigUint, // generators
+    pub p: BigUint, //prime
+    pub q: BigUint, //order of the subgroup
+}
+
+impl GroupParams {
+    /// 2048-bit MODP group with 224-bit prime order subgroup
+    /// Reference: https://www.rfc-editor.org/rfc/rfc5114#section-2.2
+    pub fn rfc5114() -> GroupParams {
+        let (a, b, p, q) = crate::ZKP::get_constants();
+        GroupParams { a, b, p, q }
+    }
+
+    /// p and q prime, q | p - 1, a and b of order q and a != b
+    pub fn validate(&self) -> Result<(), ParamError> {
+        let one = BigUint::from(1u32);
+        if !is_probable_prime(&self.p, MILLER_RABIN_ROUNDS) {
+            return Err(ParamError::PNotPrime);
+        }
+        if !is_probable_prime(&self.q, MILLER_RABIN_ROUNDS) {
+            return Err(ParamError::QNotPrime);
+        }
+        if (&self.p - &one) % &self.q != BigUint::from(0u32) {
+            return Err(ParamError::QDoesNotDividePMinusOne);
+        }
+        for (name, g) in [("a", &self.a), ("b", &self.b)] {
+            if *g <= one {
+                return Err(ParamError::TrivialGenerator(name));
+            }
+            if *g >= self.p {
+                return Err(ParamError::GeneratorOutOfRange(name));
+            }
+            if g.modpow(&self.q, &self.p) != one {
+                return Err(ParamError::GeneratorNotInSubgroup(name));
+            }
+        }
+        if self.a == self.b {
+            return Err(ParamError::GeneratorsEqual);
+        }
+        Ok(())
+    }
+}
+
+/// Miller-Rabin with random bases
+pub fn is_probable_prime(n: &BigUint, rounds: usize) -> bool {
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    // trial division takes care of small n and most composites
+    for small in [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        let small = BigUint::from(small);
+        if *n == small {
+            return true;
+        }
+        if n % &small == BigUint::from(0u32) {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^r with d odd
+    let n_minus_one = n - &one;
+    let r = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> r;
+    let mut rng = rand::thread_rng();
+    'witness: for _ in 0..rounds {
+        let base = rng.gen_biguint_range(&two, &n_minus_one);
+        let mut x = base.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..r {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// order q subgroup of Z_p^*, this is the group the protocol originally shipped with
+/// elements and scalars are sent as big endian bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModPGroup {
+    a: BigUint,
+    b: BigUint, // generators
+    p: BigUint, //prime
+    q: BigUint, //order of the subgroup
+}
+
+impl ModPGroup {
+    pub fn new(a: &BigUint, b: &BigUint, p: &BigUint, q: &BigUint) -> ModPGroup {
+        ModPGroup {
+            a: a.clone(),
+            b: b.clone(),
+            p: p.clone(),
+            q: q.clone(),
+        }
+    }
+
+    /// group for GroupParams::rfc5114
+    pub fn rfc5114() -> ModPGroup {
+        GroupParams::rfc5114().into()
+    }
+
+    pub fn params(&self) -> GroupParams {
+        GroupParams {
+            a: self.a.clone(),
+            b: self.b.clone(),
+            p: self.p.clone(),
+            q: self.q.clone(),
+        }
+    }
+
+    /// published seed b of the RFC 5114 group is derived from
+    pub const RFC5114_GENERATOR_B_SEED: &'static [u8] =
+        b"ChaumPedersen/rfc5114-2048-224/generator-b";
+    /// generator index used with the seed above
+    pub const GENERATOR_B_INDEX: u8 = 1;
+
+    /// verifiable canonical generation of a generator of the order q subgroup (FIPS 186-4 A.2.3)
+    /// W = SHA-256(seed || "ggen" || index || count), g = W^((p-1)/q) mod p, first count with g > 1 wins.
+    /// nobody picks g, so nobody knows its discrete log to any other generator
+    pub fn derive_generator(p: &BigUint, q: &BigUint, seed: &[u8], index: u8) -> Option<BigUint> {
+        let one = BigUint::from(1u32);
+        if q.bits() == 0 || (p - &one) % q != BigUint::from(0u32) {
+            return None;
+        }
+        let e = (p - &one) / q;
+        for count in 1..=u16::MAX {
+            let w = Sha256::new()
+                .chain_update(seed)
+                .chain_update(b"ggen")
+                .chain_update([index])
+                .chain_update(count.to_be_bytes())
+                .finalize();
+            let g = BigUint::from_bytes_be(&w).modpow(&e, p);
+            if g > one {
+                return Some(g);
+            }
+        }
+        None
+    }
+
+    /// re-derive b from the seed and compare
+    pub fn check_generator_b(&self, seed: &[u8]) -> bool {
+        Self::derive_generator(&self.p, &self.q, seed, Self::GENERATOR_B_INDEX).as_ref()
+            == Some(&self.b)
+    }
+
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    pub fn q(&self) -> &BigUint {
+        &self.q
+    }
+}
+
+impl From<GroupParams> for ModPGroup {
+    fn from(params: GroupParams) -> Self {
+        let GroupParams { a, b, p, q } = params;
+        ModPGroup { a, b, p, q }
+    }
+}
+
+impl Group for ModPGroup {
+    type Element = BigUint;
+    type Scalar = BigUint;
+
+    fn generators(&self) -> (&BigUint, &BigUint) {
+        (&self.a, &self.b)
+    }
+
+    fn identity(&self) -> BigUint {
+        BigUint::from(1u32)
+    }
+
+    fn exp(&self, base: &BigUint, e: &BigUint) -> BigUint {
+        base.modpow(e, &self.p)
+    }
+
+    fn mul(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        (x * y) % &self.p
+    }
+
+    fn scalar_mul(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        (x * y) % &self.q
+    }
+
+    fn scalar_sub(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        // add q first so we never go below zero with unsigned ints
+        ((x % &self.q) + &self.q - (y % &self.q)) % &self.q
+    }
+
+    fn random_scalar(&self) -> BigUint {
+        rand::thread_rng().gen_biguint_below(&self.q)
+    }
+
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> BigUint {
+        BigUint::from_bytes_be(digest) % &self.q
+    }
+
+    fn encode_params(&self) -> Vec<u8> {
+        let mut params = b"modp".to_vec();
+        for value in [&self.p, &self.q, &self.a, &self.b] {
+            let bytes = value.to_bytes_be();
+            params.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            params.extend_from_slice(&bytes);
+        }
+        params
+    }
+
+    fn encode_element(&self, element: &BigUint) -> Vec<u8> {
+        element.to_bytes_be()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<BigUint, GroupError> {
+        let element = BigUint::from_bytes_be(bytes);
+        if element == BigUint::from(0u32) {
+            return Err(GroupError::InvalidElement(
+                "zero is not in the group".to_string(),
+            ));
+        }
+        if element >= self.p {
+            return Err(GroupError::InvalidElement("not below p".to_string()));
+        }
+        // this also catches p - 1, it has order 2
+        if element.modpow(&self.q, &self.p) != BigUint::from(1u32) {
+            return Err(GroupError::InvalidElement(
+                "not in the order q subgroup".to_string(),
+            ));
+        }
+        Ok(element)
+    }
+
+    fn encode_scalar(&self, scalar: &BigUint) -> Vec<u8> {
+        scalar.to_bytes_be()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<BigUint, GroupError> {
+        let scalar = BigUint::from_bytes_be(bytes);
+        if scalar >= self.q {
+            return Err(GroupError::InvalidScalar("not reduced mod q".to_string()));
+        }
+        Ok(scalar)
+    }
+}
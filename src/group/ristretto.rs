@@ -0,0 +1,102 @@
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use sha2::Sha512;
+
+use super::{Group, GroupError};
+
+/// seed we hash to get the second generator, nobody knows log_a(b)
+const GENERATOR_B_SEED: &[u8] = b"ChaumPedersen/ristretto255/generator-b";
+
+/// prime order group built on curve25519, elements and scalars are 32 bytes on the wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RistrettoGroup {
+    a: RistrettoPoint,
+    b: RistrettoPoint,
+}
+
+impl RistrettoGroup {
+    pub fn new() -> RistrettoGroup {
+        RistrettoGroup {
+            a: RISTRETTO_BASEPOINT_POINT,
+            b: RistrettoPoint::hash_from_bytes::<Sha512>(GENERATOR_B_SEED),
+        }
+    }
+}
+
+impl Default for RistrettoGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Group for RistrettoGroup {
+    type Element = RistrettoPoint;
+    type Scalar = Scalar;
+
+    fn generators(&self) -> (&RistrettoPoint, &RistrettoPoint) {
+        (&self.a, &self.b)
+    }
+
+    fn identity(&self) -> RistrettoPoint {
+        RistrettoPoint::identity()
+    }
+
+    fn exp(&self, base: &RistrettoPoint, e: &Scalar) -> RistrettoPoint {
+        base * e
+    }
+
+    fn mul(&self, x: &RistrettoPoint, y: &RistrettoPoint) -> RistrettoPoint {
+        x + y
+    }
+
+    fn scalar_mul(&self, x: &Scalar, y: &Scalar) -> Scalar {
+        x * y
+    }
+
+    fn scalar_sub(&self, x: &Scalar, y: &Scalar) -> Scalar {
+        x - y
+    }
+
+    fn random_scalar(&self) -> Scalar {
+        Scalar::random(&mut rand::thread_rng())
+    }
+
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> Scalar {
+        Scalar::from_bytes_mod_order_wide(digest)
+    }
+
+    fn encode_params(&self) -> Vec<u8> {
+        let mut params = b"ristretto255".to_vec();
+        params.extend_from_slice(self.a.compress().as_bytes());
+        params.extend_from_slice(self.b.compress().as_bytes());
+        params
+    }
+
+    fn encode_element(&self, element: &RistrettoPoint) -> Vec<u8> {
+        element.compress().to_bytes().to_vec()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<RistrettoPoint, GroupError> {
+        let compressed = CompressedRistretto::from_slice(bytes)
+            .map_err(|_| GroupError::InvalidElement("expected 32 bytes".to_string()))?;
+        compressed
+            .decompress()
+            .ok_or_else(|| GroupError::InvalidElement("not a ristretto255 encoding".to_string()))
+    }
+
+    fn encode_scalar(&self, scalar: &Scalar) -> Vec<u8> {
+        scalar.to_bytes().to_vec()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Scalar, GroupError> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| GroupError::InvalidScalar("expected 32 bytes".to_string()))?;
+        Option::from(Scalar::from_canonical_bytes(bytes))
+            .ok_or_else(|| GroupError::InvalidScalar("not reduced mod q".to_string()))
+    }
+}
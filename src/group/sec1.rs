@@ -0,0 +1,139 @@
+use elliptic_curve::{
+    group::{cofactor::CofactorGroup, Group as _},
+    hash2curve::{ExpandMsgXmd, FromOkm, GroupDigest},
+    sec1::{EncodedPoint, FromEncodedPoint, ModulusSize, ToEncodedPoint},
+    CurveArithmetic, Field, FieldBytes, FieldBytesSize, PrimeField,
+};
+use sha2::Sha256;
+
+use super::{Group, GroupError};
+
+/// domain separation tags for hash2curve (RFC 9380)
+const GENERATOR_B_DST: &[u8] = b"ChaumPedersen-generator-b";
+const HASH_TO_SCALAR_DST: &[u8] = b"ChaumPedersen-hash-to-scalar";
+
+/// short weierstrass curves from the RustCrypto stack (P-256, secp256k1)
+/// points go over the wire SEC1 compressed (33 bytes), scalars as 32 big endian bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sec1Group<C: CurveArithmetic> {
+    a: C::ProjectivePoint,
+    b: C::ProjectivePoint,
+}
+
+#[cfg(feature = "p256")]
+pub type P256Group = Sec1Group<p256::NistP256>;
+
+#[cfg(feature = "secp256k1")]
+pub type Secp256k1Group = Sec1Group<k256::Secp256k1>;
+
+impl<C> Sec1Group<C>
+where
+    C: CurveArithmetic + GroupDigest,
+    C::ProjectivePoint: CofactorGroup,
+{
+    /// a is the standard base point, b is hashed onto the curve from a fixed tag
+    pub fn new() -> Sec1Group<C> {
+        let b = C::hash_from_bytes::<ExpandMsgXmd<Sha256>>(&[b"generator-b"], &[GENERATOR_B_DST])
+            .expect("hash2curve with a fixed input cannot fail");
+        Sec1Group {
+            a: C::ProjectivePoint::generator(),
+            b,
+        }
+    }
+}
+
+impl<C> Default for Sec1Group<C>
+where
+    C: CurveArithmetic + GroupDigest,
+    C::ProjectivePoint: CofactorGroup,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Group for Sec1Group<C>
+where
+    C: CurveArithmetic + GroupDigest,
+    C::AffinePoint: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    C::ProjectivePoint: CofactorGroup,
+    C::Scalar: FromOkm,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Element = C::ProjectivePoint;
+    type Scalar = C::Scalar;
+
+    fn generators(&self) -> (&C::ProjectivePoint, &C::ProjectivePoint) {
+        (&self.a, &self.b)
+    }
+
+    fn identity(&self) -> C::ProjectivePoint {
+        C::ProjectivePoint::identity()
+    }
+
+    fn exp(&self, base: &C::ProjectivePoint, e: &C::Scalar) -> C::ProjectivePoint {
+        *base * e
+    }
+
+    fn mul(&self, x: &C::ProjectivePoint, y: &C::ProjectivePoint) -> C::ProjectivePoint {
+        *x + y
+    }
+
+    fn scalar_mul(&self, x: &C::Scalar, y: &C::Scalar) -> C::Scalar {
+        *x * y
+    }
+
+    fn scalar_sub(&self, x: &C::Scalar, y: &C::Scalar) -> C::Scalar {
+        *x - y
+    }
+
+    fn random_scalar(&self) -> C::Scalar {
+        C::Scalar::random(&mut rand::thread_rng())
+    }
+
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> C::Scalar {
+        C::hash_to_scalar::<ExpandMsgXmd<Sha256>>(&[digest], &[HASH_TO_SCALAR_DST])
+            .expect("hash2curve with a fixed size input cannot fail")
+    }
+
+    fn encode_params(&self) -> Vec<u8> {
+        // the compressed generators already tell the curves apart
+        let mut params = b"sec1".to_vec();
+        params.extend_from_slice(&self.encode_element(&self.a));
+        params.extend_from_slice(&self.encode_element(&self.b));
+        params
+    }
+
+    fn encode_element(&self, element: &C::ProjectivePoint) -> Vec<u8> {
+        let affine: C::AffinePoint = (*element).into();
+        affine.to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<C::ProjectivePoint, GroupError> {
+        // only the compressed form, this also rules out the identity (a single 0x00 byte)
+        let point = EncodedPoint::<C>::from_bytes(bytes)
+            .ok()
+            .filter(|point| point.is_compressed())
+            .ok_or_else(|| {
+                GroupError::InvalidElement("expected a SEC1 compressed point".to_string())
+            })?;
+        // from_encoded_point checks the point is on the curve
+        Option::<C::AffinePoint>::from(C::AffinePoint::from_encoded_point(&point))
+            .map(C::ProjectivePoint::from)
+            .ok_or_else(|| GroupError::InvalidElement("point is not on the curve".to_string()))
+    }
+
+    fn encode_scalar(&self, scalar: &C::Scalar) -> Vec<u8> {
+        scalar.to_repr().to_vec()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<C::Scalar, GroupError> {
+        if bytes.len() != FieldBytes::<C>::default().len() {
+            return Err(GroupError::InvalidScalar("wrong length".to_string()));
+        }
+        Option::from(C::Scalar::from_repr(FieldBytes::<C>::clone_from_slice(
+            bytes,
+        )))
+        .ok_or_else(|| GroupError::InvalidScalar("not reduced mod q".to_string()))
+    }
+}
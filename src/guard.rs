@@ -0,0 +1,270 @@
+#![allow(clippy::result_large_err)] // tonic::Status is big, everything here answers with one anyway
+
+//! require a session from this auth server on other grpc services. callers send
+//! `authorization: Bearer <session_id or token>`, handlers read who it is with
+//! `AuthenticatedUser::from_request`
+//!
+//! tokens are checked offline against the key from GetPublicKey, sync enough for a tonic
+//! interceptor. session ids need the session store, so they go through `SessionLayer`.
+//! only session ids see Logout, RotateCredentials, DeleteAccount and RevokeSessions right
+//! away, a token stays good until its exp
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # fn run(store: Arc<ChaumPedersen::store::MemoryStore>, key: ed25519_dalek::VerifyingKey) {
+//! use ChaumPedersen::guard::{SessionLayer, TokenInterceptor, Verifier};
+//!
+//! // every service behind the layer, but logging in stays open
+//! let verifier = Verifier::sessions(store);
+//! let server = tonic::transport::Server::builder()
+//!     .layer(SessionLayer::new(verifier).allow("/zkp_auth.Auth/"));
+//! // or one service, tokens only: GreeterServer::with_interceptor(greeter, TokenInterceptor::new(key))
+//! # }
+//! ```
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ed25519_dalek::VerifyingKey;
+use tonic::{
+    body::BoxBody,
+    codegen::{http, Service},
+    service::Interceptor,
+    Request, Status,
+};
+use tower_layer::Layer;
+
+use crate::{store::SessionStore, token};
+
+/// metadata key the bearer goes in
+pub const AUTHORIZATION: &str = "authorization";
+
+/// who made the call, put in the request extensions once the bearer checks out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub name: String,
+    /// unix seconds
+    pub expires_at: u64,
+}
+
+impl AuthenticatedUser {
+    /// Unauthenticated if the request did not go through the layer or interceptor
+    pub fn from_request<T>(request: &Request<T>) -> Result<&AuthenticatedUser, Status> {
+        request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .ok_or_else(|| Status::unauthenticated("no authenticated user"))
+    }
+}
+
+/// what a bearer is checked against, a token needs the key and a session id the store
+#[derive(Clone, Default)]
+pub struct Verifier {
+    key: Option<VerifyingKey>,
+    sessions: Option<Arc<dyn SessionStore>>,
+}
+
+impl Verifier {
+    /// signed tokens only, they can't be revoked and stay good until exp even after Logout
+    pub fn tokens(key: VerifyingKey) -> Verifier {
+        Verifier::default().with_key(key)
+    }
+
+    /// session ids looked up in the auth server's store, Logout and revocation apply right away
+    pub fn sessions(store: Arc<impl SessionStore>) -> Verifier {
+        Verifier::default().with_sessions(store)
+    }
+
+    /// accept signed tokens too. careful when adding it to `sessions`: a token is checked
+    /// offline, so a caller who logged out, rotated, deleted the account or was revoked still
+    /// gets in with one until it expires. only for services that can live with that
+    pub fn with_key(mut self, key: VerifyingKey) -> Verifier {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn with_sessions(mut self, store: Arc<impl SessionStore>) -> Verifier {
+        self.sessions = Some(store);
+        self
+    }
+
+    /// a token (three dot separated parts) or a session id
+    pub async fn verify(&self, bearer: &str) -> Result<AuthenticatedUser, Status> {
+        if is_token(bearer) {
+            return self.verify_token(bearer);
+        }
+        let store = self
+            .sessions
+            .as_ref()
+            .ok_or_else(|| Status::unauthenticated("a signed token is required"))?;
+        let session = store
+            .get_session(bearer)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::unauthenticated("Session not found"))?;
+        // expired ones are left for the auth server's cleanup
+        if session.expires_at <= unix_millis() {
+            return Err(Status::unauthenticated("Session has expired"));
+        }
+        Ok(AuthenticatedUser {
+            name: session.user,
+            expires_at: session.expires_at / 1000,
+        })
+    }
+
+    pub fn verify_token(&self, bearer: &str) -> Result<AuthenticatedUser, Status> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| Status::unauthenticated("a session id is required"))?;
+        let claims = token::verify(bearer, key, unix_millis() / 1000)
+            .map_err(|e| Status::unauthenticated(e.to_string()))?;
+        Ok(AuthenticatedUser {
+            name: claims.sub,
+            expires_at: claims.exp,
+        })
+    }
+}
+
+fn is_token(bearer: &str) -> bool {
+    bearer.split('.').count() == 3
+}
+
+/// the credential from `authorization: Bearer <...>`, the scheme is case insensitive
+pub fn bearer(value: Option<&str>) -> Result<&str, Status> {
+    let value = value.ok_or_else(|| Status::unauthenticated("authorization is missing"))?;
+    match value.split_once(' ') {
+        Some((scheme, credential))
+            if scheme.eq_ignore_ascii_case("bearer") && !credential.trim().is_empty() =>
+        {
+            Ok(credential.trim())
+        }
+        _ => Err(Status::unauthenticated("authorization must be a bearer")),
+    }
+}
+
+/// tokens only, for `FooServer::with_interceptor`. interceptors are sync so they can't
+/// ask the session store, use SessionLayer for session ids
+#[derive(Clone)]
+pub struct TokenInterceptor {
+    verifier: Verifier,
+}
+
+impl TokenInterceptor {
+    pub fn new(key: VerifyingKey) -> TokenInterceptor {
+        TokenInterceptor {
+            verifier: Verifier::tokens(key),
+        }
+    }
+}
+
+impl Interceptor for TokenInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let value = request.metadata().get(AUTHORIZATION).map(|v| v.to_str());
+        let value = value
+            .transpose()
+            .map_err(|_| Status::unauthenticated("authorization is not ascii"))?;
+        let user = self.verifier.verify_token(bearer(value)?)?;
+        request.extensions_mut().insert(user);
+        Ok(request)
+    }
+}
+
+/// tower layer for `Server::builder().layer(..)`, checks tokens and session ids.
+/// calls to an allowed path prefix (e.g. "/zkp_auth.Auth/") go through without a bearer
+#[derive(Clone)]
+pub struct SessionLayer {
+    verifier: Verifier,
+    allowed: Arc<Vec<String>>,
+}
+
+impl SessionLayer {
+    pub fn new(verifier: Verifier) -> SessionLayer {
+        SessionLayer {
+            verifier,
+            allowed: Arc::new(Vec::new()),
+        }
+    }
+
+    /// let calls under this path prefix through unauthenticated
+    pub fn allow(mut self, prefix: &str) -> SessionLayer {
+        Arc::make_mut(&mut self.allowed).push(prefix.to_string());
+        self
+    }
+}
+
+impl<S> Layer<S> for SessionLayer {
+    type Service = SessionService<S>;
+
+    fn layer(&self, inner: S) -> SessionService<S> {
+        SessionService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionService<S> {
+    inner: S,
+    layer: SessionLayer,
+}
+
+impl<S, B> Service<http::Request<B>> for SessionService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // the service we polled ready is the one that has to take the call
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let path = request.uri().path();
+        if self
+            .layer
+            .allowed
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        {
+            return Box::pin(inner.call(request));
+        }
+        let verifier = self.layer.verifier.clone();
+        Box::pin(async move {
+            let value = request
+                .headers()
+                .get(AUTHORIZATION)
+                .map(|value| value.to_str().unwrap_or_default());
+            let user = match bearer(value) {
+                Ok(bearer) => verifier.verify(bearer).await,
+                Err(status) => Err(status),
+            };
+            match user {
+                Ok(user) => {
+                    request.extensions_mut().insert(user);
+                    inner.call(request).await
+                }
+                Err(status) => Ok(status.into_http()),
+            }
+        })
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock before 1970")
+        .as_millis() as u64
+}
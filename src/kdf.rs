@@ -0,0 +1,150 @@
+use std::fmt;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use crate::{DynChaumPedersen, Group};
+
+/// length of the random per-user salt
+pub const SALT_LEN: usize = 16;
+
+/// Argon2id cost parameters, stored next to the salt so they can be raised per user later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost_kib: u32, // memory in KiB
+    pub t_cost: u32,     // iterations
+    pub p_cost: u32,     // lanes
+}
+
+impl Default for KdfParams {
+    /// OWASP recommendation for Argon2id: 19 MiB, 2 iterations, 1 lane
+    fn default() -> Self {
+        KdfParams {
+            m_cost_kib: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// cheapest parameters either side accepts, so a server can't talk a client into a
+    /// hash that is quick to brute force
+    pub const MIN: KdfParams = KdfParams {
+        m_cost_kib: 8 * 1024,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    /// and the most expensive, so a server can't make a client hash with a GiB forever
+    pub const MAX: KdfParams = KdfParams {
+        m_cost_kib: 1024 * 1024,
+        t_cost: 16,
+        p_cost: 16,
+    };
+
+    /// each cost within MIN..=MAX, and a combination argon2 takes (e.g. at least 8 KiB
+    /// per lane)
+    pub fn validate(&self) -> Result<(), KdfError> {
+        let costs = [
+            (
+                "m_cost_kib",
+                self.m_cost_kib,
+                Self::MIN.m_cost_kib,
+                Self::MAX.m_cost_kib,
+            ),
+            ("t_cost", self.t_cost, Self::MIN.t_cost, Self::MAX.t_cost),
+            ("p_cost", self.p_cost, Self::MIN.p_cost, Self::MAX.p_cost),
+        ];
+        for (name, value, min, max) in costs {
+            if !(min..=max).contains(&value) {
+                return Err(KdfError::InvalidParams(format!(
+                    "{} must be between {} and {}, got {}",
+                    name, min, max, value
+                )));
+            }
+        }
+        self.argon2().map(|_| ())
+    }
+
+    /// m_cost_kib, t_cost and p_cost big endian, how they go into a proof context
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0u8; 12];
+        bytes[..4].copy_from_slice(&self.m_cost_kib.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.t_cost.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.p_cost.to_be_bytes());
+        bytes
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, KdfError> {
+        let params = Params::new(self.m_cost_kib, self.t_cost, self.p_cost, Some(64))
+            .map_err(|e| KdfError::InvalidParams(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdfError {
+    SaltTooShort,
+    InvalidParams(String),
+    Hash(String),
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::SaltTooShort => write!(f, "salt must be at least {} bytes", SALT_LEN),
+            KdfError::InvalidParams(reason) => write!(f, "invalid argon2 parameters: {}", reason),
+            KdfError::Hash(reason) => write!(f, "argon2 failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for KdfError {}
+
+/// fresh random salt for register
+pub fn generate_salt() -> Vec<u8> {
+    rand::random::<[u8; SALT_LEN]>().to_vec()
+}
+
+/// 64 bytes of Argon2id(password, salt || username), the username goes in so two accounts
+/// that share a salt by accident still get different witnesses
+fn stretch(
+    username: &str,
+    password: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<[u8; 64], KdfError> {
+    if salt.len() < SALT_LEN {
+        return Err(KdfError::SaltTooShort);
+    }
+    let mut salted = salt.to_vec();
+    salted.extend_from_slice(username.as_bytes());
+    let mut out = [0u8; 64];
+    params
+        .argon2()?
+        .hash_password_into(password, &salted, &mut out)
+        .map_err(|e| KdfError::Hash(e.to_string()))?;
+    Ok(out)
+}
+
+/// witness x mod q for (username, password, salt)
+pub fn derive_scalar<G: Group>(
+    group: &G,
+    username: &str,
+    password: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<G::Scalar, KdfError> {
+    Ok(group.scalar_from_hash(&stretch(username, password, salt, params)?))
+}
+
+/// same as derive_scalar but encoded for the group picked at runtime
+pub fn derive_secret(
+    zkp: &dyn DynChaumPedersen,
+    username: &str,
+    password: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Vec<u8>, KdfError> {
+    Ok(zkp.scalar_from_hash(&stretch(username, password, salt, params)?))
+}
@@ -1,6 +1,5 @@
-use std::collections;
+#![allow(non_snake_case)] // crate name is ChaumPedersen
 
-use hex;
 /// ChaumPedersen pick two generators from group G
 /// a, b and compute y1= a^x mod p and y2 = b^x mod p where x is witness
 /// pick a random value k and compute R1= a^k and R2 = b^k mod p (note that these values known by the both parties)
@@ -8,25 +7,398 @@ use hex;
 /// response = s = k - c*x mod q (order?) is the proof
 /// verifier will verify if R1 == a^s . y1^c and if R2 == b^s. y2^c
 use num_bigint::{BigUint, RandBigInt};
-use rand::{self, random, Rng};
+use rand::{self, Rng};
+use sha2::{Digest, Sha512};
+
+pub mod group;
+pub mod guard;
+pub mod kdf;
+pub mod sdk;
+pub mod store;
+pub mod token;
+
+/// generated from proto/zk_auth.proto by build.rs
+pub mod zkp_auth {
+    include!("./zkp_auth.rs");
+}
+
+pub use group::{Group, GroupError, GroupId, GroupParams, ModPGroup, ParamError, RistrettoGroup};
+pub use sdk::ZkAuthClient;
+
+/// domain tag for Fiat-Shamir transcripts
+const FIAT_SHAMIR_DOMAIN: &[u8] = b"ChaumPedersen/fiat-shamir/v1";
+/// context the server expects for the single shot Login rpc
+pub const LOGIN_CONTEXT: &[u8] = b"zkp_auth.Auth/Login";
+
+/// context bound into a Login proof: the server label, the client timestamp (unix seconds)
+/// and a random nonce, the server rejects stale timestamps and nonces it has already seen
+pub fn login_context(timestamp: u64, nonce: &[u8]) -> Vec<u8> {
+    let mut context = LOGIN_CONTEXT.to_vec();
+    context.extend_from_slice(&timestamp.to_be_bytes());
+    context.extend_from_slice(nonce);
+    context
+}
+
+/// context the server expects for UpdateCredentials
+pub const UPDATE_CREDENTIALS_CONTEXT: &[u8] = b"zkp_auth.Auth/UpdateCredentials";
+/// context the server expects for RotateCredentials
+pub const ROTATE_CREDENTIALS_CONTEXT: &[u8] = b"zkp_auth.Auth/RotateCredentials";
+/// context the server expects for DeleteAccount
+pub const DELETE_ACCOUNT_CONTEXT: &[u8] = b"zkp_auth.Auth/DeleteAccount";
+
+/// rpc label, timestamp, then the nonce and whatever else the proof vouches for,
+/// length prefixed since they are variable length
+fn bound_context(label: &[u8], timestamp: u64, fields: &[&[u8]]) -> Vec<u8> {
+    let mut context = label.to_vec();
+    context.extend_from_slice(&timestamp.to_be_bytes());
+    for field in fields {
+        context.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        context.extend_from_slice(field);
+    }
+    context
+}
+
+/// context bound into an UpdateCredentials proof (made with the old secret): like
+/// login_context, plus everything the new verifier is made of (group, y1, y2, salt and
+/// argon2 params) so the proof can't install anything else
+pub fn update_credentials_context(
+    timestamp: u64,
+    nonce: &[u8],
+    group: GroupId,
+    y1: &[u8],
+    y2: &[u8],
+    salt: &[u8],
+    kdf: &kdf::KdfParams,
+) -> Vec<u8> {
+    bound_context(
+        UPDATE_CREDENTIALS_CONTEXT,
+        timestamp,
+        &[
+            nonce,
+            group.to_string().as_bytes(),
+            y1,
+            y2,
+            salt,
+            &kdf.to_bytes(),
+        ],
+    )
+}
+
+/// same as update_credentials_context, for RotateCredentials
+pub fn rotate_credentials_context(
+    timestamp: u64,
+    nonce: &[u8],
+    group: GroupId,
+    y1: &[u8],
+    y2: &[u8],
+    salt: &[u8],
+    kdf: &kdf::KdfParams,
+) -> Vec<u8> {
+    bound_context(
+        ROTATE_CREDENTIALS_CONTEXT,
+        timestamp,
+        &[
+            nonce,
+            group.to_string().as_bytes(),
+            y1,
+            y2,
+            salt,
+            &kdf.to_bytes(),
+        ],
+    )
+}
+
+/// context bound into a DeleteAccount proof
+pub fn delete_account_context(timestamp: u64, nonce: &[u8]) -> Vec<u8> {
+    bound_context(DELETE_ACCOUNT_CONTEXT, timestamp, &[nonce])
+}
+
+/// prover/verifier for the Chaum-Pedersen protocol over any prime order group
+#[derive(Debug, Clone)]
+pub struct ChaumPedersen<G: Group> {
+    group: G,
+}
+
+impl<G: Group> ChaumPedersen<G> {
+    pub fn new(group: G) -> ChaumPedersen<G> {
+        ChaumPedersen { group }
+    }
+
+    pub fn group(&self) -> &G {
+        &self.group
+    }
+
+    /// y1 = a^x and y2 = b^x, sent once at register
+    pub fn public_key(&self, x: &G::Scalar) -> (G::Element, G::Element) {
+        let (a, b) = self.group.generators();
+        (self.group.exp(a, x), self.group.exp(b, x))
+    }
+
+    /// r1 = a^k and r2 = b^k for a fresh random k
+    pub fn commitment(&self, k: &G::Scalar) -> (G::Element, G::Element) {
+        self.public_key(k)
+    }
+
+    /// response s = k - c*x mod q
+    /// where k is a random number, x witness and c is the challenge given by verifier
+    pub fn proof(&self, k: &G::Scalar, c: &G::Scalar, x: &G::Scalar) -> G::Scalar {
+        self.group.scalar_sub(k, &self.group.scalar_mul(c, x))
+    }
+
+    /// non-interactive challenge c = H(group params, y1, y2, r1, r2, user, context) mod q
+    /// every field is length prefixed so two transcripts can't be shuffled into each other
+    pub fn challenge(
+        &self,
+        y1: &G::Element,
+        y2: &G::Element,
+        r1: &G::Element,
+        r2: &G::Element,
+        user: &str,
+        context: &[u8],
+    ) -> G::Scalar {
+        let mut hasher = Sha512::new();
+        let fields = [
+            FIAT_SHAMIR_DOMAIN.to_vec(),
+            self.group.encode_params(),
+            self.group.encode_element(y1),
+            self.group.encode_element(y2),
+            self.group.encode_element(r1),
+            self.group.encode_element(r2),
+            user.as_bytes().to_vec(),
+            context.to_vec(),
+        ];
+        for field in fields {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field);
+        }
+        self.group.scalar_from_hash(&hasher.finalize().into())
+    }
+
+    /// prover side of the non-interactive mode, returns (r1, r2, s)
+    /// with c computed from the transcript instead of being sent by the verifier
+    pub fn prove_non_interactive(
+        &self,
+        x: &G::Scalar,
+        user: &str,
+        context: &[u8],
+    ) -> (G::Element, G::Element, G::Scalar) {
+        let (y1, y2) = self.public_key(x);
+        let k = self.group.random_scalar();
+        let (r1, r2) = self.commitment(&k);
+        let c = self.challenge(&y1, &y2, &r1, &r2, user, context);
+        let s = self.proof(&k, &c, x);
+        (r1, r2, s)
+    }
+
+    /// verifier side of the non-interactive mode
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_non_interactive(
+        &self,
+        y1: &G::Element,
+        y2: &G::Element,
+        r1: &G::Element,
+        r2: &G::Element,
+        s: &G::Scalar,
+        user: &str,
+        context: &[u8],
+    ) -> bool {
+        let c = self.challenge(y1, y2, r1, r2, user, context);
+        self.verify(y1, y2, r1, r2, &c, s)
+    }
+
+    /// verifier will verify if r1 == a^s . y1^c and if r2 == b^s . y2^c
+    pub fn verify(
+        &self,
+        y1: &G::Element,
+        y2: &G::Element,
+        r1: &G::Element,
+        r2: &G::Element,
+        c: &G::Scalar,
+        s: &G::Scalar,
+    ) -> bool {
+        let (a, b) = self.group.generators();
+        let left = *r1
+            == self
+                .group
+                .mul(&self.group.exp(a, s), &self.group.exp(y1, c));
+        let right = *r2
+            == self
+                .group
+                .mul(&self.group.exp(b, s), &self.group.exp(y2, c));
+        left && right
+    }
+}
+
+/// same protocol but everything is wire bytes, so the server can pick the group
+/// per user at runtime instead of at compile time
+pub trait DynChaumPedersen: Send + Sync {
+    fn group_id(&self) -> GroupId;
+    /// y1, y2, r1, r2 must decode to a group element other than the identity
+    fn validate_element(&self, bytes: &[u8]) -> Result<(), GroupError>;
+    fn random_scalar(&self) -> Vec<u8>;
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> Vec<u8>;
+    fn public_key(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>), GroupError>;
+    fn commitment(&self, k: &[u8]) -> Result<(Vec<u8>, Vec<u8>), GroupError>;
+    fn proof(&self, k: &[u8], c: &[u8], x: &[u8]) -> Result<Vec<u8>, GroupError>;
+    fn challenge(
+        &self,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        user: &str,
+        context: &[u8],
+    ) -> Result<Vec<u8>, GroupError>;
+    fn verify(
+        &self,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        c: &[u8],
+        s: &[u8],
+    ) -> Result<bool, GroupError>;
+}
+
+struct Tagged<G: Group> {
+    id: GroupId,
+    inner: ChaumPedersen<G>,
+}
+
+impl<G: Group> Tagged<G> {
+    /// the identity as y or r would make the proof hold for any x (or leak it), never accept it
+    fn decode_element(&self, bytes: &[u8]) -> Result<G::Element, GroupError> {
+        let group = self.inner.group();
+        let element = group.decode_element(bytes)?;
+        if element == group.identity() {
+            return Err(GroupError::InvalidElement("identity element".to_string()));
+        }
+        Ok(element)
+    }
+}
+
+impl<G> DynChaumPedersen for Tagged<G>
+where
+    G: Group + Send + Sync,
+{
+    fn group_id(&self) -> GroupId {
+        self.id
+    }
+
+    fn validate_element(&self, bytes: &[u8]) -> Result<(), GroupError> {
+        self.decode_element(bytes).map(|_| ())
+    }
+
+    fn random_scalar(&self) -> Vec<u8> {
+        let group = self.inner.group();
+        group.encode_scalar(&group.random_scalar())
+    }
+
+    fn scalar_from_hash(&self, digest: &[u8; 64]) -> Vec<u8> {
+        let group = self.inner.group();
+        group.encode_scalar(&group.scalar_from_hash(digest))
+    }
+
+    fn public_key(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>), GroupError> {
+        let group = self.inner.group();
+        let (y1, y2) = self.inner.public_key(&group.decode_scalar(x)?);
+        Ok((group.encode_element(&y1), group.encode_element(&y2)))
+    }
+
+    fn commitment(&self, k: &[u8]) -> Result<(Vec<u8>, Vec<u8>), GroupError> {
+        self.public_key(k)
+    }
+
+    fn proof(&self, k: &[u8], c: &[u8], x: &[u8]) -> Result<Vec<u8>, GroupError> {
+        let group = self.inner.group();
+        let s = self.inner.proof(
+            &group.decode_scalar(k)?,
+            &group.decode_scalar(c)?,
+            &group.decode_scalar(x)?,
+        );
+        Ok(group.encode_scalar(&s))
+    }
+
+    fn challenge(
+        &self,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        user: &str,
+        context: &[u8],
+    ) -> Result<Vec<u8>, GroupError> {
+        let group = self.inner.group();
+        let c = self.inner.challenge(
+            &self.decode_element(y1)?,
+            &self.decode_element(y2)?,
+            &self.decode_element(r1)?,
+            &self.decode_element(r2)?,
+            user,
+            context,
+        );
+        Ok(group.encode_scalar(&c))
+    }
+
+    fn verify(
+        &self,
+        y1: &[u8],
+        y2: &[u8],
+        r1: &[u8],
+        r2: &[u8],
+        c: &[u8],
+        s: &[u8],
+    ) -> Result<bool, GroupError> {
+        let group = self.inner.group();
+        Ok(self.inner.verify(
+            &self.decode_element(y1)?,
+            &self.decode_element(y2)?,
+            &self.decode_element(r1)?,
+            &self.decode_element(r2)?,
+            &group.decode_scalar(c)?,
+            &group.decode_scalar(s)?,
+        ))
+    }
+}
+
+impl GroupId {
+    /// protocol instance for this group with the default generators
+    pub fn protocol(&self) -> Box<dyn DynChaumPedersen> {
+        match self {
+            GroupId::ModP2048 => Box::new(Tagged {
+                id: *self,
+                inner: ChaumPedersen::new(ModPGroup::rfc5114()),
+            }),
+            GroupId::Ristretto255 => Box::new(Tagged {
+                id: *self,
+                inner: ChaumPedersen::new(RistrettoGroup::new()),
+            }),
+            #[cfg(feature = "p256")]
+            GroupId::P256 => Box::new(Tagged {
+                id: *self,
+                inner: ChaumPedersen::new(group::P256Group::new()),
+            }),
+            #[cfg(feature = "secp256k1")]
+            GroupId::Secp256k1 => Box::new(Tagged {
+                id: *self,
+                inner: ChaumPedersen::new(group::Secp256k1Group::new()),
+            }),
+        }
+    }
+}
+
 // refactor and add static & global params in the struct for brevity
-#[derive(Clone)]
+// kept around as the BigUint front end of ChaumPedersen<ModPGroup>
+#[derive(Debug, Clone)]
 pub struct ZKP {
-    a: BigUint,
-    b: BigUint, // generators
-    p: BigUint, //prime
-    q: BigUint, //order of the function
+    inner: ChaumPedersen<ModPGroup>,
 }
 
 impl ZKP {
     pub fn init(a: &BigUint, b: &BigUint, p: &BigUint, q: &BigUint) -> ZKP {
-        let zkp = ZKP {
-            a: a.clone(),
-            b: b.clone(),
-            p: p.clone(),
-            q: q.clone(),
-        };
-        zkp
+        ZKP {
+            inner: ChaumPedersen::new(ModPGroup::new(a, b, p, q)),
+        }
     }
     // calculate the g^x mod p
     // using the default modpow function in BigInt
@@ -34,15 +406,11 @@ impl ZKP {
         num.modpow(exp, p)
     }
 
-    /// in chaum_pedersen we have response s = k - c*x mod p
+    /// in chaum_pedersen we have response s = k - c*x mod q
     /// where k is a random number, x witness and c is the challenge given by verifier
     ///
     pub fn proof(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
-        if *k >= c * x {
-            return (k - c * x).modpow(&BigUint::from(1u32), &self.q);
-        } else {
-            return &self.q - (c * x - k).modpow(&BigUint::from(1u32), &self.q); // k < cx
-        }
+        self.inner.proof(k, c, x)
     }
 
     /// verifier will verify if R1 == a^s . y1^c mod p and if R2 == b^s. y2^c mod p
@@ -55,19 +423,7 @@ impl ZKP {
         c: &BigUint,  // challenge
         s: &BigUint,  // response
     ) -> bool {
-        let left = *r1
-            == Self::mod_exp(
-                &(Self::mod_exp(&self.a, &s, &self.p) * Self::mod_exp(y1, &c, &self.p)),
-                &BigUint::from(1u32),
-                &self.p,
-            );
-        let right = *r2
-            == Self::mod_exp(
-                &(Self::mod_exp(&self.b, &s, &self.p) * Self::mod_exp(y2, &c, &self.p)),
-                &BigUint::from(1u32),
-                &self.p,
-            );
-        left && right
+        self.inner.verify(y1, y2, r1, r2, c, s)
     }
     /// generate random binguint
     pub fn gen_rand(max: &BigUint) -> BigUint {
@@ -86,11 +442,13 @@ impl ZKP {
             &hex::decode("AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98AE247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA").unwrap(),
         );
 
-        // let b = ZKP::gen_rand(&q);
+        // b must not be a known power of a, otherwise y1 and y2 leak the same thing and we are
+        // back to plain Schnorr. it is derived from a published seed, see ModPGroup::derive_generator
+        // and ModPGroup::RFC5114_GENERATOR_B_SEED, the test_generator_b test re-derives it
         let b = BigUint::from_bytes_be(
-            &hex::decode("AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98AE247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA").unwrap(),
+            &hex::decode("7C1CEF5A187AF67457F24C1A8308EEC5918827EBE470FFB820305B8116E36D1625730571FC5F994E8124E3402D602C2FC2642B0ABAD90F981C7A6BC9D59D5B75FE3E450036A2CB921DF2122A9AEADE1AB51A1168DFE665E09CC1507FA6FBD129C0F9290B753B107CB3461FF5E246D0CA4E4E159DF298A14DBE685D9999BC8C46C90F5784069AC4812AA3C1852D50A82920483F3143563E2832B6C21AD5AEFAF84A7D84D39E6D51188098FC0A7C8BA451C36FE30D5AB65FFF6D45014BF428246F6A776C492808CE6D68DA91A68D19AEBAC47986F8AC8885A1863F8C178EF73F8C9D2B9C6CF0A12C1F3C689338B376D490E04D9615D41231178771DB1F4B4FF2B9").unwrap(),
         );
-        return (a, b, p, q);
+        (a, b, p, q)
     }
     // directly from docs
     pub fn gen_rand_string(size: usize) -> String {
@@ -218,7 +576,7 @@ mod test {
         // );
         // let b = ZKP::gen_rand(&q);
 
-        let (p, q, a, b) = ZKP::get_constants();
+        let (a, b, p, q) = ZKP::get_constants();
 
         // set a witness value.
         let w = ZKP::gen_rand(&q);
@@ -249,4 +607,773 @@ mod test {
         let verif = zkp.verify(&y1, &y2, &r1, &r2, &c, &s);
         assert!(verif);
     }
+
+    #[test]
+    fn test_chaum_pedersen_modp() {
+        let group = ModPGroup::rfc5114();
+        let cp = ChaumPedersen::new(group.clone());
+
+        let x = group.random_scalar();
+        let (y1, y2) = cp.public_key(&x);
+
+        let k = group.random_scalar();
+        let (r1, r2) = cp.commitment(&k);
+        let c = group.random_scalar();
+        let s = cp.proof(&k, &c, &x);
+
+        // everything goes over the wire as bytes
+        let y1 = group.decode_element(&group.encode_element(&y1)).unwrap();
+        let s = group.decode_scalar(&group.encode_scalar(&s)).unwrap();
+        assert!(cp.verify(&y1, &y2, &r1, &r2, &c, &s));
+
+        // a response for another witness must fail
+        let wrong = cp.proof(&k, &c, &group.random_scalar());
+        assert!(!cp.verify(&y1, &y2, &r1, &r2, &c, &wrong));
+    }
+
+    #[test]
+    fn test_ristretto() {
+        let group = RistrettoGroup::new();
+        let cp = ChaumPedersen::new(group.clone());
+        let (a, b) = group.generators();
+        assert_ne!(a, b);
+
+        let x = group.random_scalar();
+        let (y1, y2) = cp.public_key(&x);
+        let k = group.random_scalar();
+        let (r1, r2) = cp.commitment(&k);
+        let c = group.random_scalar();
+        let s = cp.proof(&k, &c, &x);
+        assert!(cp.verify(&y1, &y2, &r1, &r2, &c, &s));
+
+        // 32 bytes for every value on the wire
+        assert_eq!(group.encode_element(&y1).len(), 32);
+        assert_eq!(group.encode_scalar(&s).len(), 32);
+        assert!(group.decode_element(&[0xffu8; 32]).is_err());
+        assert!(group.decode_scalar(&[0xffu8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_dyn_protocol_all_groups() {
+        for id in GroupId::ALL {
+            let zkp = id.protocol();
+            assert_eq!(zkp.group_id(), *id);
+            assert_eq!(id.to_string().parse::<GroupId>().unwrap(), *id);
+
+            let x = zkp.random_scalar();
+            let (y1, y2) = zkp.public_key(&x).unwrap();
+            let k = zkp.random_scalar();
+            let (r1, r2) = zkp.commitment(&k).unwrap();
+            let c = zkp.random_scalar();
+            let s = zkp.proof(&k, &c, &x).unwrap();
+            assert!(zkp.verify(&y1, &y2, &r1, &r2, &c, &s).unwrap());
+        }
+    }
+
+    /// curve y^2 = x^3 + ax + b over F_p, hex
+    #[cfg(any(feature = "p256", feature = "secp256k1"))]
+    struct Curve {
+        p: &'static str,
+        a: &'static str,
+        b: &'static str,
+    }
+
+    #[cfg(any(feature = "p256", feature = "secp256k1"))]
+    fn check_sec1_group<G: Group>(group: G, curve: Curve) {
+        let cp = ChaumPedersen::new(group);
+        let group = cp.group();
+        let x = group.random_scalar();
+        let (y1, y2) = cp.public_key(&x);
+        let k = group.random_scalar();
+        let (r1, r2) = cp.commitment(&k);
+        let c = group.random_scalar();
+        let s = cp.proof(&k, &c, &x);
+        assert!(cp.verify(&y1, &y2, &r1, &r2, &c, &s));
+
+        // compressed on the wire
+        let encoded = group.encode_element(&y1);
+        assert_eq!(encoded.len(), 33);
+        assert_eq!(group.decode_element(&encoded).unwrap(), y1);
+
+        // the other y for the same x is -y1
+        let mut negated = encoded.clone();
+        negated[0] ^= 1;
+        let negated = group.decode_element(&negated).unwrap();
+        assert_eq!(group.mul(&y1, &negated), group.identity());
+
+        // an x with no point above it: x^3 + ax + b is not a square mod p
+        let hex = |hex: &str| BigUint::parse_bytes(hex.as_bytes(), 16).unwrap();
+        let (p, a, b) = (hex(curve.p), hex(curve.a), hex(curve.b));
+        let half = (&p - 1u32) >> 1;
+        let off_curve = (1u32..)
+            .map(BigUint::from)
+            .find(|x| (x.pow(3) + &a * x + &b).modpow(&half, &p) == &p - 1u32)
+            .unwrap();
+        for tag in [0x02u8, 0x03] {
+            let mut encoded = vec![0u8; 33];
+            encoded[0] = tag;
+            let x = off_curve.to_bytes_be();
+            encoded[33 - x.len()..].copy_from_slice(&x);
+            assert!(group.decode_element(&encoded).is_err());
+        }
+
+        // tags other than compressed, on an x that is on the curve
+        for tag in [0x00u8, 0x04, 0x05] {
+            let mut bad_tag = encoded.clone();
+            bad_tag[0] = tag;
+            assert!(group.decode_element(&bad_tag).is_err());
+        }
+        // identity and truncated input
+        assert!(group.decode_element(&[0u8]).is_err());
+        assert!(group.decode_element(&encoded[..32]).is_err());
+        // scalars must be canonical
+        assert!(group.decode_scalar(&[0xffu8; 32]).is_err());
+    }
+
+    #[cfg(feature = "p256")]
+    #[test]
+    fn test_p256() {
+        check_sec1_group(
+            group::P256Group::new(),
+            Curve {
+                p: "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+                a: "ffffffff00000001000000000000000000000000fffffffffffffffffffffffc",
+                b: "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+            },
+        );
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_secp256k1() {
+        check_sec1_group(
+            group::Secp256k1Group::new(),
+            Curve {
+                p: "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+                a: "0",
+                b: "7",
+            },
+        );
+    }
+
+    #[test]
+    fn test_non_interactive() {
+        let group = RistrettoGroup::new();
+        let cp = ChaumPedersen::new(group.clone());
+        let x = group.random_scalar();
+        let (y1, y2) = cp.public_key(&x);
+        let context = login_context(1_700_000_000, b"0123456789abcdef");
+
+        let (r1, r2, s) = cp.prove_non_interactive(&x, "alice", &context);
+        assert!(cp.verify_non_interactive(&y1, &y2, &r1, &r2, &s, "alice", &context));
+
+        // the proof is bound to the user and the context
+        assert!(!cp.verify_non_interactive(&y1, &y2, &r1, &r2, &s, "bob", &context));
+        let other = login_context(1_700_000_001, b"0123456789abcdef");
+        assert!(!cp.verify_non_interactive(&y1, &y2, &r1, &r2, &s, "alice", &other));
+
+        // and to the group, the transcript starts with the group parameters
+        assert_ne!(ModPGroup::rfc5114().encode_params(), group.encode_params());
+    }
+
+    #[test]
+    fn test_generator_b() {
+        let group = ModPGroup::rfc5114();
+        let (a, b) = group.generators();
+        assert_ne!(a, b);
+
+        // anyone can re-derive b from the published seed
+        let derived = ModPGroup::derive_generator(
+            group.p(),
+            group.q(),
+            ModPGroup::RFC5114_GENERATOR_B_SEED,
+            ModPGroup::GENERATOR_B_INDEX,
+        )
+        .unwrap();
+        assert_eq!(&derived, b);
+        assert!(group.check_generator_b(ModPGroup::RFC5114_GENERATOR_B_SEED));
+        assert!(!group.check_generator_b(b"some other seed"));
+        assert_eq!(group.params().validate(), Ok(()));
+
+        // the old parameters had b == a
+        let mut broken = group.params();
+        broken.b = a.clone();
+        assert_eq!(broken.validate(), Err(ParamError::GeneratorsEqual));
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let params = GroupParams::rfc5114();
+        assert_eq!(params.validate(), Ok(()));
+        // toy group from the tests above
+        let toy = GroupParams {
+            a: BigUint::from(4u32),
+            b: BigUint::from(9u32),
+            p: BigUint::from(23u32),
+            q: BigUint::from(11u32),
+        };
+        assert_eq!(toy.validate(), Ok(()));
+
+        let mut broken = toy.clone();
+        broken.p = BigUint::from(25u32);
+        assert_eq!(broken.validate(), Err(ParamError::PNotPrime));
+        let mut broken = toy.clone();
+        broken.q = BigUint::from(21u32);
+        assert_eq!(broken.validate(), Err(ParamError::QNotPrime));
+        let mut broken = toy.clone();
+        broken.q = BigUint::from(7u32);
+        assert_eq!(broken.validate(), Err(ParamError::QDoesNotDividePMinusOne));
+        let mut broken = toy.clone();
+        broken.a = BigUint::from(1u32);
+        assert_eq!(broken.validate(), Err(ParamError::TrivialGenerator("a")));
+        let mut broken = toy.clone();
+        broken.b = BigUint::from(27u32);
+        assert_eq!(broken.validate(), Err(ParamError::GeneratorOutOfRange("b")));
+        // 5 generates all of Z_23^*, order 22
+        let mut broken = toy.clone();
+        broken.b = BigUint::from(5u32);
+        assert_eq!(
+            broken.validate(),
+            Err(ParamError::GeneratorNotInSubgroup("b"))
+        );
+        // same with the real modulus, 2 is not in the order q subgroup
+        let mut broken = params.clone();
+        broken.a = BigUint::from(2u32);
+        assert_eq!(
+            broken.validate(),
+            Err(ParamError::GeneratorNotInSubgroup("a"))
+        );
+
+        // 3215031751 = 151 * 751 * 28351 has no factor trial division looks for and is a
+        // strong pseudoprime to bases 2, 3, 5 and 7, only random miller-rabin bases catch it
+        assert!(!group::is_probable_prime(
+            &BigUint::from(3_215_031_751u64),
+            16
+        ));
+        assert!(group::is_probable_prime(&BigUint::from(7919u32), 16));
+    }
+
+    #[test]
+    fn test_kdf() {
+        use kdf::{derive_scalar, derive_secret, generate_salt, KdfError, KdfParams};
+        // cheapest parameters allowed so the test stays fast
+        let params = KdfParams::MIN;
+        let group = RistrettoGroup::new();
+        let salt = generate_salt();
+
+        let x = derive_scalar(&group, "alice", b"hunter2", &salt, &params).unwrap();
+        // deterministic
+        assert_eq!(
+            x,
+            derive_scalar(&group, "alice", b"hunter2", &salt, &params).unwrap()
+        );
+        // every input matters
+        let other_salt = generate_salt();
+        assert_ne!(
+            x,
+            derive_scalar(&group, "bob", b"hunter2", &salt, &params).unwrap()
+        );
+        assert_ne!(
+            x,
+            derive_scalar(&group, "alice", b"hunter3", &salt, &params).unwrap()
+        );
+        assert_ne!(
+            x,
+            derive_scalar(&group, "alice", b"hunter2", &other_salt, &params).unwrap()
+        );
+        let slower = KdfParams {
+            t_cost: 2,
+            ..params
+        };
+        assert_ne!(
+            x,
+            derive_scalar(&group, "alice", b"hunter2", &salt, &slower).unwrap()
+        );
+
+        // the dyn version agrees with the typed one
+        let zkp = GroupId::Ristretto255.protocol();
+        assert_eq!(
+            derive_secret(zkp.as_ref(), "alice", b"hunter2", &salt, &params).unwrap(),
+            group.encode_scalar(&x)
+        );
+        // witness is reduced mod q for the 224-bit group too
+        let modp = ModPGroup::rfc5114();
+        let x = derive_scalar(&modp, "alice", b"a", &salt, &params).unwrap();
+        assert!(&x < modp.q());
+
+        assert_eq!(
+            derive_scalar(&group, "alice", b"hunter2", &salt[..8], &params),
+            Err(KdfError::SaltTooShort)
+        );
+        // both ends of each bound are fine, one past them is not
+        assert!(KdfParams::MIN.validate().is_ok());
+        assert!(KdfParams::MAX.validate().is_ok());
+        assert!(KdfParams::default().validate().is_ok());
+        let min = KdfParams::MIN;
+        let max = KdfParams::MAX;
+        for params in [
+            KdfParams {
+                m_cost_kib: min.m_cost_kib - 1,
+                ..min
+            },
+            KdfParams { t_cost: 0, ..min },
+            KdfParams { p_cost: 0, ..min },
+            KdfParams {
+                m_cost_kib: max.m_cost_kib + 1,
+                ..max
+            },
+            KdfParams {
+                t_cost: max.t_cost + 1,
+                ..max
+            },
+            KdfParams {
+                p_cost: max.p_cost + 1,
+                ..max
+            },
+        ] {
+            assert!(
+                matches!(params.validate(), Err(KdfError::InvalidParams(_))),
+                "{:?}",
+                params
+            );
+        }
+    }
+
+    #[test]
+    fn test_token() {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+        use token::{verify, Claims, TokenError, TokenSigner, AMR_INTERACTIVE};
+
+        let signer = TokenSigner::generate();
+        let claims = Claims {
+            sub: "alice".to_string(),
+            iat: 1_000,
+            exp: 1_060,
+            amr: vec![AMR_INTERACTIVE.to_string()],
+        };
+        let token = signer.sign(&claims);
+        assert_eq!(
+            verify(&token, &signer.public_key(), 1_059),
+            Ok(claims.clone())
+        );
+        assert_eq!(
+            verify(&token, &signer.public_key(), 1_060),
+            Err(TokenError::Expired)
+        );
+
+        // someone else's key
+        let other = TokenSigner::generate();
+        assert_eq!(
+            verify(&token, &other.public_key(), 1_000),
+            Err(TokenError::UnknownKey(signer.key_id().to_string()))
+        );
+
+        // swap in different claims, keep the signature
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = Claims {
+            sub: "mallory".to_string(),
+            ..claims.clone()
+        };
+        let forged_claims = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        let tampered = format!("{}.{}.{}", parts[0], forged_claims, parts[2]);
+        assert_eq!(
+            verify(&tampered, &signer.public_key(), 1_000),
+            Err(TokenError::BadSignature)
+        );
+
+        // alg none is not a thing here
+        let none = URL_SAFE_NO_PAD.encode(format!(
+            r#"{{"alg":"none","typ":"JWT","kid":"{}"}}"#,
+            signer.key_id()
+        ));
+        let unsigned = format!("{}.{}.", none, parts[1]);
+        assert_eq!(
+            verify(&unsigned, &signer.public_key(), 1_000),
+            Err(TokenError::UnsupportedAlgorithm("none".to_string()))
+        );
+        assert!(matches!(
+            verify("not a token", &signer.public_key(), 1_000),
+            Err(TokenError::Malformed(_))
+        ));
+
+        // the key survives a trip through the key file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token_key.pem");
+        let stored = TokenSigner::load_or_generate(&path).unwrap();
+        let loaded = TokenSigner::load_or_generate(&path).unwrap();
+        assert_eq!(stored.key_id(), loaded.key_id());
+        assert_eq!(
+            verify(&stored.sign(&claims), &loaded.public_key(), 1_000),
+            Ok(claims)
+        );
+        assert!(matches!(
+            TokenSigner::from_pem("garbage"),
+            Err(TokenError::Key(_))
+        ));
+    }
+
+    // the UserStore contract every backend has to keep
+    async fn check_user_store<S: store::UserStore>(store: &S) {
+        use store::User;
+
+        let alice = User {
+            name: "alice".to_string(),
+            group: GroupId::Ristretto255,
+            y1: vec![1; 32],
+            y2: vec![2; 32],
+            salt: vec![3; kdf::SALT_LEN],
+            kdf: kdf::KdfParams::default(),
+        };
+        assert_eq!(store.get("alice").await.unwrap(), None);
+        store.put(alice.clone()).await.unwrap();
+        assert_eq!(store.get("alice").await.unwrap(), Some(alice.clone()));
+
+        // put overwrites
+        let alice2 = User {
+            y1: vec![4; 32],
+            ..alice.clone()
+        };
+        store.put(alice2.clone()).await.unwrap();
+        assert_eq!(store.get("alice").await.unwrap(), Some(alice2.clone()));
+
+        // compare and swap only goes through against the current value
+        let bob = User {
+            name: "bob".to_string(),
+            ..alice.clone()
+        };
+        assert!(!store
+            .compare_and_swap("alice", Some(&alice), Some(alice.clone()))
+            .await
+            .unwrap());
+        assert!(!store
+            .compare_and_swap("alice", None, Some(alice.clone()))
+            .await
+            .unwrap());
+        assert!(store
+            .compare_and_swap("alice", Some(&alice2), Some(alice.clone()))
+            .await
+            .unwrap());
+        assert_eq!(store.get("alice").await.unwrap(), Some(alice.clone()));
+        assert!(store
+            .compare_and_swap("bob", None, Some(bob.clone()))
+            .await
+            .unwrap());
+
+        let mut names: Vec<String> = store
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["alice", "bob"]);
+
+        // cas to None deletes
+        assert!(store
+            .compare_and_swap("bob", Some(&bob), None)
+            .await
+            .unwrap());
+        assert_eq!(store.get("bob").await.unwrap(), None);
+        assert!(store.delete("alice").await.unwrap());
+        assert!(!store.delete("alice").await.unwrap());
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    // challenges and sessions, same for every backend
+    async fn check_challenge_and_session_store<S: store::ChallengeStore + store::SessionStore>(
+        store: &S,
+    ) {
+        use store::{Challenge, Session};
+
+        let challenge = Challenge {
+            user: "alice".to_string(),
+            r1: vec![1; 32],
+            r2: vec![2; 32],
+            c: vec![3; 32],
+            created_at: 1_000,
+            attempts: 0,
+        };
+        assert_eq!(store.answer_challenge("a").await.unwrap(), None);
+        store.put_challenge("a", challenge.clone()).await.unwrap();
+        store
+            .put_challenge(
+                "b",
+                Challenge {
+                    created_at: 2_000,
+                    ..challenge.clone()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_challenge("a").await.unwrap(),
+            Some(challenge.clone())
+        );
+        // every answer counts
+        for attempts in 1..=2 {
+            assert_eq!(
+                store.answer_challenge("a").await.unwrap(),
+                Some(Challenge {
+                    attempts,
+                    ..challenge.clone()
+                })
+            );
+        }
+        assert_eq!(store.purge_challenges(2_000).await.unwrap(), 1);
+        assert_eq!(store.get_challenge("a").await.unwrap(), None);
+        assert!(store.get_challenge("b").await.unwrap().is_some());
+
+        let session = Session {
+            user: "alice".to_string(),
+            expires_at: 5_000,
+            auth_method: token::AMR_INTERACTIVE.to_string(),
+        };
+        store.put_session("s1", session.clone()).await.unwrap();
+        store
+            .put_session(
+                "s2",
+                Session {
+                    expires_at: 9_000,
+                    ..session.clone()
+                },
+            )
+            .await
+            .unwrap();
+        store
+            .put_session(
+                "s3",
+                Session {
+                    expires_at: 9_000,
+                    ..session.clone()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_session("s1").await.unwrap(),
+            Some(session.clone())
+        );
+        assert_eq!(
+            store.take_session("s1").await.unwrap(),
+            Some(session.clone())
+        );
+        assert_eq!(store.take_session("s1").await.unwrap(), None);
+        assert!(store.delete_session("s2").await.unwrap());
+        assert!(!store.delete_session("s2").await.unwrap());
+        assert_eq!(store.purge_sessions(8_999).await.unwrap(), 0);
+        assert_eq!(store.purge_sessions(9_000).await.unwrap(), 1);
+        assert_eq!(store.get_session("s3").await.unwrap(), None);
+
+        // revoking a user leaves everybody else alone
+        let bob = Session {
+            user: "bob".to_string(),
+            ..session.clone()
+        };
+        for (id, owned) in [("s4", &session), ("s5", &session), ("s6", &bob)] {
+            store.put_session(id, owned.clone()).await.unwrap();
+        }
+        let bob_challenge = Challenge {
+            user: "bob".to_string(),
+            ..challenge
+        };
+        store
+            .put_challenge("c", bob_challenge.clone())
+            .await
+            .unwrap();
+        assert_eq!(store.delete_user_sessions("alice").await.unwrap(), 2);
+        assert_eq!(store.delete_user_sessions("alice").await.unwrap(), 0);
+        assert_eq!(store.get_session("s6").await.unwrap(), Some(bob));
+        assert_eq!(store.delete_user_challenges("alice").await.unwrap(), 1);
+        assert_eq!(store.get_challenge("b").await.unwrap(), None);
+        assert_eq!(store.get_challenge("c").await.unwrap(), Some(bob_challenge));
+
+        // a nonce goes through once, until it is purged
+        assert!(store.use_nonce(b"nonce-1", 5_000).await.unwrap());
+        assert!(!store.use_nonce(b"nonce-1", 9_000).await.unwrap());
+        assert!(store.use_nonce(b"nonce-2", 9_000).await.unwrap());
+        assert_eq!(store.purge_nonces(5_000).await.unwrap(), 1);
+        assert!(store.use_nonce(b"nonce-1", 9_000).await.unwrap());
+        assert!(!store.use_nonce(b"nonce-2", 9_000).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = store::MemoryStore::new();
+        check_user_store(&store).await;
+        check_challenge_and_session_store(&store).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        use store::{SqliteStore, StoreError, UserStore};
+
+        let store = SqliteStore::open_in_memory().unwrap();
+        check_user_store(&store).await;
+        check_challenge_and_session_store(&store).await;
+
+        // a file survives being closed and opened again
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zkp.db");
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), 3);
+        let alice = store::User {
+            name: "alice".to_string(),
+            group: GroupId::ModP2048,
+            y1: vec![1; 256],
+            y2: vec![2; 256],
+            salt: vec![3; kdf::SALT_LEN],
+            kdf: kdf::KdfParams::default(),
+        };
+        store.put(alice.clone()).await.unwrap();
+        drop(store);
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), 3);
+        assert_eq!(store.get("alice").await.unwrap(), Some(alice));
+        drop(store);
+
+        // don't touch a database a newer server has migrated
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", 99).unwrap();
+        drop(conn);
+        assert!(matches!(
+            SqliteStore::open(&path),
+            Err(StoreError::Corrupt(_))
+        ));
+    }
+
+    /// what the crash writer stores as user number i, sizes vary so values straddle pages
+    #[cfg(feature = "kv")]
+    fn crash_user(i: usize) -> store::User {
+        store::User {
+            name: format!("user-{:06}", i),
+            group: GroupId::Ristretto255,
+            y1: vec![i as u8; 32 + i % 500],
+            y2: vec![!(i as u8); 32 + i % 300],
+            salt: (i as u64).to_be_bytes().repeat(2),
+            kdf: kdf::KdfParams::default(),
+        }
+    }
+
+    /// writes users until it gets killed, only runs when test_kv_store_crash starts it
+    #[cfg(feature = "kv")]
+    #[tokio::test]
+    #[ignore]
+    async fn kv_crash_writer() {
+        use store::{KvStore, UserStore};
+
+        let Ok(path) = std::env::var("ZKP_KV_CRASH_WRITER") else {
+            return;
+        };
+        let store = KvStore::open(path).unwrap();
+        let mut i = store.list().await.unwrap().len();
+        loop {
+            store.put(crash_user(i)).await.unwrap();
+            i += 1;
+        }
+    }
+
+    #[cfg(feature = "kv")]
+    #[tokio::test]
+    async fn test_kv_store() {
+        use store::{ChallengeStore, KvStore, SessionStore, StoreError, UserStore};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zkp.redb");
+        let store = KvStore::open(&path).unwrap();
+        check_user_store(&store).await;
+        check_challenge_and_session_store(&store).await;
+
+        // a file survives being closed and opened again
+        let alice = crash_user(7);
+        store.put(alice.clone()).await.unwrap();
+        drop(store);
+        let store = KvStore::open(&path).unwrap();
+        assert_eq!(store.get(&alice.name).await.unwrap(), Some(alice.clone()));
+        drop(store);
+
+        // values a newer server wrote, or that got cut short, are reported and not misread
+        let table: redb::TableDefinition<&str, &[u8]> = redb::TableDefinition::new("users");
+        for value in [&[99u8, 0, 0][..], &[1, 0, 0, 0, 5, b'b'][..], &[][..]] {
+            let db = redb::Database::create(&path).unwrap();
+            let tx = db.begin_write().unwrap();
+            tx.open_table(table).unwrap().insert("bob", value).unwrap();
+            tx.commit().unwrap();
+            drop(db);
+            let store = KvStore::open(&path).unwrap();
+            assert!(matches!(
+                store.get("bob").await,
+                Err(StoreError::Corrupt(_))
+            ));
+            assert!(matches!(store.list().await, Err(StoreError::Corrupt(_))));
+            assert_eq!(store.get(&alice.name).await.unwrap(), Some(alice.clone()));
+        }
+
+        // a garbage value next to expired ones does not stop the purges, it is skipped
+        let store = KvStore::open(&path).unwrap();
+        let session = |expires_at| store::Session {
+            user: "alice".to_string(),
+            expires_at,
+            auth_method: token::AMR_INTERACTIVE.to_string(),
+        };
+        store.put_session("old", session(10)).await.unwrap();
+        store.put_session("live", session(30)).await.unwrap();
+        store.use_nonce(b"old nonce", 10).await.unwrap();
+        store.use_nonce(b"live nonce", 30).await.unwrap();
+        drop(store);
+        let db = redb::Database::create(&path).unwrap();
+        let tx = db.begin_write().unwrap();
+        for name in ["sessions", "nonces"] {
+            let table: redb::TableDefinition<&str, &[u8]> = redb::TableDefinition::new(name);
+            tx.open_table(table)
+                .unwrap()
+                .insert("garbage", &[99u8, 1, 2][..])
+                .unwrap();
+        }
+        tx.commit().unwrap();
+        drop(db);
+        let store = KvStore::open(&path).unwrap();
+        assert_eq!(store.purge_sessions(20).await.unwrap(), 1);
+        assert_eq!(store.get_session("old").await.unwrap(), None);
+        assert_eq!(store.get_session("live").await.unwrap(), Some(session(30)));
+        assert_eq!(store.purge_nonces(20).await.unwrap(), 1);
+        assert!(store.use_nonce(b"old nonce", 40).await.unwrap());
+        assert!(!store.use_nonce(b"live nonce", 40).await.unwrap());
+    }
+
+    /// kill a process in the middle of writing users, open the file again and check that
+    /// every user in it is whole and none is missing from the middle
+    #[cfg(feature = "kv")]
+    #[tokio::test]
+    async fn test_kv_store_crash() {
+        use std::{process::Command, time::Duration};
+        use store::{KvStore, UserStore};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zkp.redb");
+        let mut written = 0;
+        for round in 0..5 {
+            let mut writer = Command::new(std::env::current_exe().unwrap())
+                .args(["test::kv_crash_writer", "--exact", "--ignored", "--quiet"])
+                .env("ZKP_KV_CRASH_WRITER", &path)
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap();
+            let wait = rand::thread_rng().gen_range(200..600) + round * 50;
+            tokio::time::sleep(Duration::from_millis(wait)).await;
+            writer.kill().unwrap();
+            writer.wait().unwrap();
+
+            let store = KvStore::open(&path).unwrap();
+            let users = store.list().await.unwrap();
+            for (i, user) in users.iter().enumerate() {
+                assert_eq!(*user, crash_user(i), "round {}", round);
+            }
+            // nothing committed before the kill went missing
+            assert!(users.len() >= written, "round {}", round);
+            written = users.len();
+        }
+        assert!(written > 0, "the writer never got to commit anything");
+    }
 }
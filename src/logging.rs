@@ -0,0 +1,67 @@
+use tonic::{codegen::http, transport::server::TcpConnectInfo};
+use tracing::{field, Span, Subscriber};
+use tracing_subscriber::{
+    filter::EnvFilter,
+    fmt::{format::FmtSpan, MakeWriter},
+};
+
+use crate::config::LogFormat;
+
+/// RUST_LOG if it is set, with the full directive syntax, otherwise the configured level
+pub fn filter(log_level: &str) -> Result<EnvFilter, String> {
+    match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::try_new(directives).map_err(|e| format!("RUST_LOG: {}", e)),
+        Err(_) => EnvFilter::try_new(log_level).map_err(|e| format!("log_level: {}", e)),
+    }
+}
+
+/// events as they happen, plus a line with the timings whenever an rpc span closes
+pub fn subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(writer);
+    match format {
+        LogFormat::Text => Box::new(builder.finish()),
+        // fields at the top level next to the span they came from, one object per line
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .flatten_event(true)
+                .with_span_list(false)
+                .finish(),
+        ),
+    }
+}
+
+/// for `Server::trace_fn`, one span per call. the handlers fill in user and auth_id once
+/// they know them, never anything from the proof or a session id
+pub fn rpc_span(request: &http::Request<()>) -> Span {
+    let peer = request
+        .extensions()
+        .get::<TcpConnectInfo>()
+        .and_then(|info| info.remote_addr());
+    tracing::info_span!(
+        "rpc",
+        rpc = request.uri().path(),
+        peer = peer.map(|peer| field::display(peer.ip())),
+        user = field::Empty,
+        auth_id = field::Empty,
+    )
+}
+
+/// who the current rpc is about
+pub fn record_user(user: &str) {
+    Span::current().record("user", user);
+}
+
+pub fn record_auth_id(auth_id: &str) {
+    Span::current().record("auth_id", auth_id);
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// token bucket: up to `burst` calls at once, refilled at `per_minute`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// how hard GetSalt, CreateAuthenticationChallenge, VerifyAuthentication, Login and the
+/// proofs for UpdateCredentials, RotateCredentials and DeleteAccount may be hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    pub user: Rate,
+    pub peer: Rate,
+    /// failed verifications in a row before a user or peer is locked out
+    pub lockout_after: u32,
+    /// first lockout, doubles with every failure after that
+    pub lockout_base: Duration,
+    pub lockout_max: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            user: Rate {
+                burst: 10,
+                per_minute: 30,
+            },
+            // several users can sit behind one address
+            peer: Rate {
+                burst: 50,
+                per_minute: 120,
+            },
+            lockout_after: 5,
+            lockout_base: Duration::from_secs(30),
+            lockout_max: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    tokens: f64,
+    refilled: Instant,
+    // failed verifications in a row
+    failures: u32,
+    locked_until: Option<Instant>,
+    last_failure: Option<Instant>,
+}
+
+impl Entry {
+    fn new(rate: Rate, now: Instant) -> Entry {
+        Entry {
+            tokens: rate.burst as f64,
+            refilled: now,
+            failures: 0,
+            locked_until: None,
+            last_failure: None,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * rate.per_minute as f64 / 60.0).min(rate.burst as f64);
+        self.refilled = now;
+    }
+
+    // how long until the next call goes through, None if it would right now
+    fn wait(&self, rate: Rate, now: Instant) -> Option<Duration> {
+        match self.locked_until {
+            Some(until) if until > now => Some(until - now),
+            _ if self.tokens < 1.0 => Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) * 60.0 / rate.per_minute as f64,
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Table<K> {
+    rate: Rate,
+    entries: HashMap<K, Entry>,
+}
+
+impl<K: Hash + Eq> Table<K> {
+    fn new(rate: Rate) -> Table<K> {
+        Table {
+            rate,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn entry(&mut self, key: K, now: Instant) -> &mut Entry {
+        let rate = self.rate;
+        let entry = self
+            .entries
+            .entry(key)
+            .or_insert_with(|| Entry::new(rate, now));
+        entry.refill(rate, now);
+        entry
+    }
+}
+
+/// per user and per peer address, checked on every login attempt. a failed proof counts
+/// towards a lockout for both, a valid one clears them
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: Limits,
+    users: Mutex<Table<String>>,
+    peers: Mutex<Table<IpAddr>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Limits) -> RateLimiter {
+        RateLimiter {
+            users: Mutex::new(Table::new(limits.user)),
+            peers: Mutex::new(Table::new(limits.peer)),
+            limits,
+        }
+    }
+
+    /// take one call from both buckets, or how long to wait when either is empty or
+    /// locked out. nothing is taken from one bucket when the other says no
+    pub fn check(
+        &self,
+        user: Option<&str>,
+        peer: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        // always users before peers, so two checks can't deadlock
+        let mut users = self.users.lock().unwrap();
+        let mut peers = self.peers.lock().unwrap();
+        let (user_rate, peer_rate) = (users.rate, peers.rate);
+        let user = user.map(|user| users.entry(user.to_string(), now));
+        let peer = peer.map(|peer| peers.entry(peer, now));
+        let wait = [
+            user.as_deref().and_then(|entry| entry.wait(user_rate, now)),
+            peer.as_deref().and_then(|entry| entry.wait(peer_rate, now)),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+        for entry in [user, peer].into_iter().flatten() {
+            entry.tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    /// how long `user` is still locked out, without taking anything from its bucket
+    pub fn locked(&self, user: &str, now: Instant) -> Result<(), Duration> {
+        let users = self.users.lock().unwrap();
+        match users.entries.get(user).and_then(|entry| entry.locked_until) {
+            Some(until) if until > now => Err(until - now),
+            _ => Ok(()),
+        }
+    }
+
+    /// a proof did not verify, locks out after `lockout_after` in a row
+    pub fn failed(&self, user: Option<&str>, peer: Option<IpAddr>, now: Instant) {
+        let mut users = self.users.lock().unwrap();
+        let mut peers = self.peers.lock().unwrap();
+        let user = user.map(|user| users.entry(user.to_string(), now));
+        let peer = peer.map(|peer| peers.entry(peer, now));
+        for entry in [user, peer].into_iter().flatten() {
+            entry.failures += 1;
+            entry.last_failure = Some(now);
+            if let Some(lockout) = self.lockout(entry.failures) {
+                entry.locked_until = Some(now + lockout);
+            }
+        }
+    }
+
+    /// a proof verified, the failures so far are forgiven
+    pub fn succeeded(&self, user: Option<&str>, peer: Option<IpAddr>) {
+        let mut users = self.users.lock().unwrap();
+        let mut peers = self.peers.lock().unwrap();
+        let user = user.and_then(|user| users.entries.get_mut(user));
+        let peer = peer.and_then(|peer| peers.entries.get_mut(&peer));
+        for entry in [user, peer].into_iter().flatten() {
+            entry.failures = 0;
+            entry.locked_until = None;
+        }
+    }
+
+    // lockout_base, doubled for every failure past lockout_after, up to lockout_max
+    fn lockout(&self, failures: u32) -> Option<Duration> {
+        let past = failures.checked_sub(self.limits.lockout_after)?;
+        let lockout = self
+            .limits
+            .lockout_base
+            .saturating_mul(2u32.saturating_pow(past.min(31)));
+        Some(lockout.min(self.limits.lockout_max))
+    }
+
+    /// forget whoever has a full bucket and no failure within lockout_max, they would
+    /// start from the same place anyway. keeps made up user names from piling up
+    pub fn purge(&self, now: Instant) {
+        let forget = self.limits.lockout_max;
+        fn purge<K>(table: &mut Table<K>, now: Instant, forget: Duration) {
+            let rate = table.rate;
+            table.entries.retain(|_, entry| {
+                entry.refill(rate, now);
+                let recent_failure = entry
+                    .last_failure
+                    .is_some_and(|last| now.saturating_duration_since(last) < forget);
+                entry.tokens < rate.burst as f64 || recent_failure
+            });
+        }
+        purge(&mut self.users.lock().unwrap(), now, forget);
+        purge(&mut self.peers.lock().unwrap(), now, forget);
+    }
+}
@@ -0,0 +1,707 @@
+#![allow(clippy::result_large_err)] // tonic::Status is big, ClientError carries it as is
+
+//! the prover side of zkp_auth.Auth for other services: key derivation, commitments and
+//! proofs happen in here, callers only deal with user names, passwords and sessions
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), ChaumPedersen::sdk::ClientError> {
+//! use ChaumPedersen::ZkAuthClient;
+//!
+//! let mut client = ZkAuthClient::connect("http://127.0.0.1:50051").await?;
+//! client.register("alice", "hunter2").await?;
+//! let session = client.login("alice", "hunter2").await?;
+//! client.logout(&session).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ed25519_dalek::VerifyingKey;
+use http_body_util::{BodyExt, Full};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tonic::{
+    body::{boxed, BoxBody},
+    codegen::{http, Body, Bytes, Service, StdError},
+    transport::Channel,
+    Code, Status,
+};
+use tower_layer::Layer;
+
+use crate::{
+    delete_account_context,
+    kdf::{derive_secret, generate_salt, KdfError, KdfParams},
+    login_context, rotate_credentials_context,
+    token::{self, Claims, TokenError},
+    zkp_auth::{
+        auth_client::AuthClient, authorization::Method, Argon2Params, AuthenticationAnswerRequest,
+        AuthenticationChallengeRequest, Authorization, DeleteAccountRequest, LoginRequest,
+        LogoutRequest, Proof, PublicKeyRequest, RefreshSessionRequest, RegisterRequest,
+        RotateCredentialsRequest, SaltRequest, ValidateSessionRequest,
+    },
+    DynChaumPedersen, GroupId,
+};
+
+/// what a successful login hands back
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub user: String,
+    /// what the server checks, keep it secret
+    pub session_id: String,
+    /// signed token for services that check offline, see token::verify
+    pub token: String,
+    /// unix seconds, from the expires_in the server sent
+    pub expires_at: u64,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("user", &self.user)
+            .field("session_id", &"<redacted>")
+            .field("token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl Session {
+    fn new(user: &str, session_id: String, token: String, expires_in: u64) -> Session {
+        Session {
+            user: user.to_string(),
+            session_id,
+            token,
+            expires_at: unix_now() + expires_in,
+        }
+    }
+
+    /// seconds left, 0 once it has expired
+    pub fn expires_in(&self) -> u64 {
+        self.expires_at.saturating_sub(unix_now())
+    }
+}
+
+/// how rotate_credentials and delete_account prove it is really the user asking
+#[derive(Debug, Clone, Copy)]
+pub enum Credential<'a> {
+    /// a fresh proof with the current password
+    Password(&'a str),
+    /// a live session of the same user
+    Session(&'a Session),
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// bad server url or the server could not be reached
+    Connect(String),
+    /// Register: the name is taken
+    AlreadyExists(String),
+    /// no such user
+    NotFound(String),
+    /// the proof did not verify (wrong password) or the session belongs to someone else
+    PermissionDenied(String),
+    /// the session is gone, expired or was revoked
+    Unauthenticated(String),
+    /// the password could not be stretched, e.g. the server sent unusable argon2 parameters
+    Kdf(KdfError),
+    /// the session token did not verify against the server's key
+    Token(TokenError),
+    /// the server sent something we can't use (unknown group, invalid challenge or key)
+    InvalidResponse(String),
+    /// any other status from the server
+    Rpc(Status),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Connect(reason) => write!(f, "could not connect: {}", reason),
+            ClientError::AlreadyExists(reason)
+            | ClientError::NotFound(reason)
+            | ClientError::PermissionDenied(reason)
+            | ClientError::Unauthenticated(reason) => f.write_str(reason),
+            ClientError::Kdf(e) => write!(f, "key derivation failed: {}", e),
+            ClientError::Token(e) => write!(f, "token did not verify: {}", e),
+            ClientError::InvalidResponse(reason) => write!(f, "server sent {}", reason),
+            ClientError::Rpc(status) => write!(f, "{:?}: {}", status.code(), status.message()),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        let message = status.message().to_string();
+        match status.code() {
+            Code::AlreadyExists => ClientError::AlreadyExists(message),
+            Code::NotFound => ClientError::NotFound(message),
+            Code::PermissionDenied => ClientError::PermissionDenied(message),
+            Code::Unauthenticated => ClientError::Unauthenticated(message),
+            _ => ClientError::Rpc(status),
+        }
+    }
+}
+
+impl From<KdfError> for ClientError {
+    fn from(e: KdfError) -> Self {
+        ClientError::Kdf(e)
+    }
+}
+
+impl From<TokenError> for ClientError {
+    fn from(e: TokenError) -> Self {
+        ClientError::Token(e)
+    }
+}
+
+/// AuthClient plus the prover, register and rotate use `group` and `kdf` for new verifiers
+#[derive(Debug, Clone)]
+pub struct ZkAuthClient<T = Channel> {
+    client: AuthClient<T>,
+    group: GroupId,
+    kdf: KdfParams,
+}
+
+impl ZkAuthClient<Channel> {
+    /// plaintext only. for TLS build the Channel with a `ClientTlsConfig` yourself and hand
+    /// it to `ZkAuthClient::new`, there are no system roots to trust by default
+    pub async fn connect(server: &str) -> Result<Self, ClientError> {
+        if server.starts_with("https://") {
+            return Err(ClientError::Connect(format!(
+                "{}: https needs a Channel with a ClientTlsConfig, see ZkAuthClient::new",
+                server
+            )));
+        }
+        let channel = Channel::from_shared(server.to_string())
+            .map_err(|e| ClientError::Connect(e.to_string()))?
+            .connect()
+            .await
+            .map_err(|e| ClientError::Connect(e.to_string()))?;
+        Ok(ZkAuthClient::new(channel))
+    }
+}
+
+impl<T> ZkAuthClient<T>
+where
+    T: tonic::client::GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// ristretto255 and the default argon2 cost for new registrations
+    pub fn new(inner: T) -> Self {
+        ZkAuthClient {
+            client: AuthClient::new(inner),
+            group: GroupId::Ristretto255,
+            kdf: KdfParams::default(),
+        }
+    }
+
+    pub fn with_group(mut self, group: GroupId) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// the generated client, for rpcs not wrapped here
+    pub fn inner(&mut self) -> &mut AuthClient<T> {
+        &mut self.client
+    }
+
+    /// fails with AlreadyExists if the name is taken
+    pub async fn register(&mut self, user: &str, password: &str) -> Result<(), ClientError> {
+        let request = self.register_request(user, password)?;
+        self.client.register(request).await?;
+        Ok(())
+    }
+
+    /// challenge + answer, two round trips
+    pub async fn login(&mut self, user: &str, password: &str) -> Result<Session, ClientError> {
+        let (zk, x) = self.derive(user, password).await?;
+        // lets generate r1 and r2
+        let k = zk.random_scalar();
+        let (r1, r2) = zk.commitment(&k).map_err(invalid)?;
+        let challenge = self
+            .client
+            .create_authentication_challenge(AuthenticationChallengeRequest {
+                user: user.to_string(),
+                r1,
+                r2,
+            })
+            .await?
+            .into_inner();
+        // s = k - c.x shows we know x without sharing it
+        let s = zk
+            .proof(&k, &challenge.c, &x)
+            .map_err(|e| ClientError::InvalidResponse(format!("an invalid challenge: {}", e)))?;
+        let answer = self
+            .client
+            .verify_authentication(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s,
+            })
+            .await?
+            .into_inner();
+        Ok(Session::new(
+            user,
+            answer.session_id,
+            answer.token,
+            answer.expires_in,
+        ))
+    }
+
+    /// one Login call, c comes from hashing the transcript (Fiat-Shamir)
+    pub async fn login_fiat_shamir(
+        &mut self,
+        user: &str,
+        password: &str,
+    ) -> Result<Session, ClientError> {
+        let (zk, x) = self.derive(user, password).await?;
+        let proof = prove(zk.as_ref(), &x, user, login_context)?;
+        let login = self
+            .client
+            .login(LoginRequest {
+                user: user.to_string(),
+                r1: proof.r1,
+                r2: proof.r2,
+                s: proof.s,
+                timestamp: proof.timestamp,
+                nonce: proof.nonce,
+            })
+            .await?
+            .into_inner();
+        Ok(Session::new(
+            user,
+            login.session_id,
+            login.token,
+            login.expires_in,
+        ))
+    }
+
+    /// the session as the server sees it now, Unauthenticated once it is gone
+    pub async fn validate(&mut self, session: &Session) -> Result<Session, ClientError> {
+        let validated = self
+            .client
+            .validate_session(ValidateSessionRequest {
+                session_id: session.session_id.clone(),
+            })
+            .await?
+            .into_inner();
+        Ok(Session::new(
+            &validated.user,
+            session.session_id.clone(),
+            session.token.clone(),
+            validated.expires_in,
+        ))
+    }
+
+    /// a new session_id and token with a fresh expiry, the old session stops working
+    pub async fn refresh(&mut self, session: &Session) -> Result<Session, ClientError> {
+        let refreshed = self
+            .client
+            .refresh_session(RefreshSessionRequest {
+                session_id: session.session_id.clone(),
+            })
+            .await?
+            .into_inner();
+        Ok(Session::new(
+            &session.user,
+            refreshed.session_id,
+            refreshed.token,
+            refreshed.expires_in,
+        ))
+    }
+
+    pub async fn logout(&mut self, session: &Session) -> Result<(), ClientError> {
+        self.client
+            .logout(LogoutRequest {
+                session_id: session.session_id.clone(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// the key tokens are signed with and its id
+    pub async fn public_key(&mut self) -> Result<(VerifyingKey, String), ClientError> {
+        let key = self
+            .client
+            .get_public_key(PublicKeyRequest {})
+            .await?
+            .into_inner();
+        let public_key = key
+            .public_key
+            .as_slice()
+            .try_into()
+            .ok()
+            .and_then(|bytes| VerifyingKey::from_bytes(bytes).ok())
+            .ok_or_else(|| ClientError::InvalidResponse("an invalid token key".to_string()))?;
+        Ok((public_key, key.key_id))
+    }
+
+    /// check a token offline the way a downstream service would
+    pub async fn verify_token(&mut self, token: &str) -> Result<Claims, ClientError> {
+        let (public_key, _) = self.public_key().await?;
+        Ok(token::verify(token, &public_key, unix_now())?)
+    }
+
+    /// new password (and the client's group), logs the user out everywhere
+    pub async fn rotate_credentials(
+        &mut self,
+        user: &str,
+        credential: Credential<'_>,
+        new_password: &str,
+    ) -> Result<(), ClientError> {
+        let new = self.register_request(user, new_password)?;
+        // copies, self is borrowed mutably while the context is made
+        let (group, kdf) = (self.group, self.kdf);
+        let authorization = self
+            .authorize(user, credential, |timestamp, nonce| {
+                rotate_credentials_context(
+                    timestamp, nonce, group, &new.y1, &new.y2, &new.salt, &kdf,
+                )
+            })
+            .await?;
+        self.client
+            .rotate_credentials(RotateCredentialsRequest {
+                user: new.user,
+                authorization: Some(authorization),
+                y1: new.y1,
+                y2: new.y2,
+                group: new.group,
+                salt: new.salt,
+                kdf: new.kdf,
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_account(
+        &mut self,
+        user: &str,
+        credential: Credential<'_>,
+    ) -> Result<(), ClientError> {
+        let authorization = self
+            .authorize(user, credential, delete_account_context)
+            .await?;
+        self.client
+            .delete_account(DeleteAccountRequest {
+                user: user.to_string(),
+                authorization: Some(authorization),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn authorize(
+        &mut self,
+        user: &str,
+        credential: Credential<'_>,
+        context: impl FnOnce(u64, &[u8]) -> Vec<u8>,
+    ) -> Result<Authorization, ClientError> {
+        let method = match credential {
+            Credential::Password(password) => {
+                let (zk, x) = self.derive(user, password).await?;
+                Method::Proof(prove(zk.as_ref(), &x, user, context)?)
+            }
+            Credential::Session(session) => Method::SessionId(session.session_id.clone()),
+        };
+        Ok(Authorization {
+            method: Some(method),
+        })
+    }
+
+    // the password is stretched with Argon2id and a fresh salt into a scalar mod q
+    fn register_request(&self, user: &str, password: &str) -> Result<RegisterRequest, ClientError> {
+        let zk = self.group.protocol();
+        let salt = generate_salt();
+        let x = derive_secret(zk.as_ref(), user, password.as_bytes(), &salt, &self.kdf)?;
+        // generate y1, y2
+        let (y1, y2) = zk.public_key(&x).map_err(invalid)?;
+        Ok(RegisterRequest {
+            user: user.to_string(),
+            y1,
+            y2,
+            group: self.group.to_string(),
+            salt,
+            kdf: Some(Argon2Params {
+                m_cost_kib: self.kdf.m_cost_kib,
+                t_cost: self.kdf.t_cost,
+                p_cost: self.kdf.p_cost,
+            }),
+        })
+    }
+
+    // every proof starts from scratch: ask the server for the salt and derive x again
+    async fn derive(
+        &mut self,
+        user: &str,
+        password: &str,
+    ) -> Result<(Box<dyn DynChaumPedersen>, Vec<u8>), ClientError> {
+        let salt_info = self
+            .client
+            .get_salt(SaltRequest {
+                user: user.to_string(),
+            })
+            .await?
+            .into_inner();
+        let zk = salt_info
+            .group
+            .parse::<GroupId>()
+            .map_err(ClientError::InvalidResponse)?
+            .protocol();
+        let kdf = salt_info
+            .kdf
+            .map(|kdf| KdfParams {
+                m_cost_kib: kdf.m_cost_kib,
+                t_cost: kdf.t_cost,
+                p_cost: kdf.p_cost,
+            })
+            .unwrap_or_default();
+        // the server picks these, don't hash with something silly because it said so
+        kdf.validate()?;
+        let x = derive_secret(
+            zk.as_ref(),
+            user,
+            password.as_bytes(),
+            &salt_info.salt,
+            &kdf,
+        )?;
+        Ok((zk, x))
+    }
+}
+
+/// what AutoLogin logs in with, asked for again on every login so it can come from a
+/// secret store that rotates
+#[derive(Clone)]
+pub struct Credentials {
+    pub user: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+pub trait CredentialProvider: Send + Sync + 'static {
+    fn credentials(&self) -> Result<Credentials, ClientError>;
+}
+
+impl<F> CredentialProvider for F
+where
+    F: Fn() -> Result<Credentials, ClientError> + Send + Sync + 'static,
+{
+    fn credentials(&self) -> Result<Credentials, ClientError> {
+        self()
+    }
+}
+
+/// keeps one session alive for long running agents: logs in on first use, again once the
+/// session is about to expire, and again when a call comes back Unauthenticated (the call
+/// is then retried once). as a tower layer it puts `authorization: Bearer <session_id>`
+/// on every request, for the services behind a guard::SessionLayer
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use ChaumPedersen::sdk::{AutoLogin, Credentials, ZkAuthClient};
+///
+/// let channel = tonic::transport::Channel::from_static("http://127.0.0.1:50051").connect().await?;
+/// let auto = AutoLogin::new(ZkAuthClient::new(channel.clone()), || {
+///     Ok(Credentials {
+///         user: "agent".to_string(),
+///         password: std::env::var("AGENT_PASSWORD").unwrap_or_default(),
+///     })
+/// });
+/// let guarded = tower_layer::Layer::layer(&auto, channel);
+/// // GreeterClient::new(guarded).say_hello(..)
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AutoLogin {
+    client: Arc<Mutex<ZkAuthClient>>,
+    session: Arc<Mutex<Option<Session>>>,
+    provider: Arc<dyn CredentialProvider>,
+    refresh_before: Duration,
+}
+
+impl AutoLogin {
+    /// logs in again 60s before the session runs out
+    pub fn new(client: ZkAuthClient, provider: impl CredentialProvider) -> AutoLogin {
+        AutoLogin {
+            client: Arc::new(Mutex::new(client)),
+            session: Arc::new(Mutex::new(None)),
+            provider: Arc::new(provider),
+            refresh_before: Duration::from_secs(60),
+        }
+    }
+
+    pub fn refresh_before(mut self, margin: Duration) -> AutoLogin {
+        self.refresh_before = margin;
+        self
+    }
+
+    /// the current session, logging in first if there is none or it is about to expire
+    pub async fn session(&self) -> Result<Session, ClientError> {
+        self.session_replacing(None).await
+    }
+
+    // callers that saw `stale` rejected need a new one, unless someone else already
+    // logged in while they waited for the lock
+    async fn session_replacing(&self, stale: Option<&str>) -> Result<Session, ClientError> {
+        let mut current = self.session.lock().await;
+        if let Some(session) = current.as_ref() {
+            let replaced = stale.is_some_and(|stale| stale != session.session_id);
+            let usable = stale.is_none() && session.expires_in() > self.refresh_before.as_secs();
+            if replaced || usable {
+                return Ok(session.clone());
+            }
+        }
+        let Credentials { user, password } = self.provider.credentials()?;
+        let session = self
+            .client
+            .lock()
+            .await
+            .login_fiat_shamir(&user, &password)
+            .await?;
+        *current = Some(session.clone());
+        Ok(session)
+    }
+}
+
+impl<S> Layer<S> for AutoLogin {
+    type Service = AutoLoginService<S>;
+
+    fn layer(&self, inner: S) -> AutoLoginService<S> {
+        AutoLoginService {
+            inner,
+            auto: self.clone(),
+        }
+    }
+}
+
+/// request bodies are buffered so a rejected call can be sent again, fine for unary rpcs
+#[derive(Clone)]
+pub struct AutoLoginService<S> {
+    inner: S,
+    auto: AutoLogin,
+}
+
+impl<S> Service<http::Request<BoxBody>> for AutoLoginService<S>
+where
+    S: Service<http::Request<BoxBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        // the service we polled ready is the one that has to take the call
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let auto = self.auto.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = match body.collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(status) => return Ok(status.into_http()),
+            };
+            let resend = |session: &Session| {
+                let mut request = http::Request::new(boxed(Full::new(body.clone())));
+                *request.method_mut() = parts.method.clone();
+                *request.uri_mut() = parts.uri.clone();
+                *request.version_mut() = parts.version;
+                *request.headers_mut() = parts.headers.clone();
+                bearer(request, session)
+            };
+
+            let session = match auto.session().await {
+                Ok(session) => session,
+                Err(e) => return Ok(login_failed(e)),
+            };
+            let response = inner.call(resend(&session)).await?;
+            let rejected = Status::from_header_map(response.headers())
+                .is_some_and(|status| status.code() == Code::Unauthenticated);
+            if !rejected {
+                return Ok(response);
+            }
+            // revoked or expired early, one more try with a new session
+            let session = match auto.session_replacing(Some(&session.session_id)).await {
+                Ok(session) => session,
+                Err(e) => return Ok(login_failed(e)),
+            };
+            std::future::poll_fn(|cx| inner.poll_ready(cx)).await?;
+            inner.call(resend(&session)).await
+        })
+    }
+}
+
+fn bearer<B>(mut request: http::Request<B>, session: &Session) -> http::Request<B> {
+    if let Ok(value) = format!("Bearer {}", session.session_id).parse() {
+        request.headers_mut().insert("authorization", value);
+    }
+    request
+}
+
+// the caller sees the failed login as the status of its own call
+fn login_failed(e: ClientError) -> http::Response<BoxBody> {
+    Status::unauthenticated(format!("login failed: {}", e)).into_http()
+}
+
+// Fiat-Shamir proof over `context(timestamp, nonce)`, r1, r2 and s go out together
+fn prove(
+    zk: &dyn DynChaumPedersen,
+    x: &[u8],
+    user: &str,
+    context: impl FnOnce(u64, &[u8]) -> Vec<u8>,
+) -> Result<Proof, ClientError> {
+    let (y1, y2) = zk.public_key(x).map_err(invalid)?;
+    let timestamp = unix_now();
+    let nonce: [u8; 16] = rand::random();
+    let k = zk.random_scalar();
+    let (r1, r2) = zk.commitment(&k).map_err(invalid)?;
+    let c = zk
+        .challenge(&y1, &y2, &r1, &r2, user, &context(timestamp, &nonce))
+        .map_err(invalid)?;
+    let s = zk.proof(&k, &c, x).map_err(invalid)?;
+    Ok(Proof {
+        r1,
+        r2,
+        s,
+        timestamp,
+        nonce: nonce.to_vec(),
+    })
+}
+
+// the values above come from the server's salt and group, so a group error is on its side
+fn invalid(e: impl fmt::Display) -> ClientError {
+    ClientError::InvalidResponse(format!("unusable parameters: {}", e))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock before 1970")
+        .as_secs()
+}
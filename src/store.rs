@@ -0,0 +1,205 @@
+use std::{fmt, sync::Arc};
+
+use crate::{kdf::KdfParams, GroupId};
+
+#[cfg(feature = "kv")]
+mod kv;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "kv")]
+pub use kv::KvStore;
+pub use memory::MemoryStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+/// a registered verifier, values are kept in their wire encoding, the group decides how to read them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct User {
+    // register
+    pub name: String,
+    pub group: GroupId,
+    pub y1: Vec<u8>,
+    pub y2: Vec<u8>,
+    // what the client needs to derive x again
+    pub salt: Vec<u8>,
+    pub kdf: KdfParams,
+}
+
+/// one pending interactive login, keyed by auth_id so several devices can log in at the same time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub user: String,
+    // commitment
+    pub r1: Vec<u8>,
+    pub r2: Vec<u8>,
+    // challenge
+    pub c: Vec<u8>,
+    /// unix milliseconds
+    pub created_at: u64,
+    /// answers received for this auth_id, only the first one is checked
+    pub attempts: u32,
+}
+
+/// handed out once a proof verifies, keyed by session_id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub user: String,
+    /// unix milliseconds
+    pub expires_at: u64,
+    /// token::AMR_*, carried over on refresh
+    pub auth_method: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    /// the backend failed, the caller can't tell whether a write went through
+    Backend(String),
+    /// something is stored that we can't read back
+    Corrupt(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Backend(reason) => write!(f, "storage backend failed: {}", reason),
+            StoreError::Corrupt(reason) => write!(f, "stored data is corrupt: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// where registered users live, keyed by user name
+#[tonic::async_trait]
+pub trait UserStore: Send + Sync + 'static {
+    async fn get(&self, name: &str) -> Result<Option<User>, StoreError>;
+    /// insert or overwrite
+    async fn put(&self, user: User) -> Result<(), StoreError>;
+    /// true if the user was there
+    async fn delete(&self, name: &str) -> Result<bool, StoreError>;
+    async fn list(&self) -> Result<Vec<User>, StoreError>;
+    /// replace what is stored under `name` with `new` only if it still equals `current`,
+    /// None on either side means absent. false when someone else got there first
+    async fn compare_and_swap(
+        &self,
+        name: &str,
+        current: Option<&User>,
+        new: Option<User>,
+    ) -> Result<bool, StoreError>;
+}
+
+/// pending challenges, keyed by auth_id
+#[tonic::async_trait]
+pub trait ChallengeStore: Send + Sync + 'static {
+    async fn put_challenge(&self, auth_id: &str, challenge: Challenge) -> Result<(), StoreError>;
+    async fn get_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError>;
+    /// count one more answer and return the challenge as it is after that, in one step
+    /// so two answers racing for the same auth_id can't both see attempts == 1
+    async fn answer_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError>;
+    /// drop challenges created before `created_before` (unix ms), returns how many
+    async fn purge_challenges(&self, created_before: u64) -> Result<usize, StoreError>;
+    /// drop every challenge handed out to `user`, returns how many
+    async fn delete_user_challenges(&self, user: &str) -> Result<usize, StoreError>;
+    /// remember the nonce of a one shot proof (Login, UpdateCredentials, ...) until
+    /// `expires_at` (unix ms). false if it is already there, in one step so two requests
+    /// with the same nonce can't both get through. kept with the challenges so a replay
+    /// is still caught after a restart
+    async fn use_nonce(&self, nonce: &[u8], expires_at: u64) -> Result<bool, StoreError>;
+    /// drop nonces that expired at or before `now` (unix ms), returns how many
+    async fn purge_nonces(&self, now: u64) -> Result<usize, StoreError>;
+}
+
+/// live sessions, keyed by session_id
+#[tonic::async_trait]
+pub trait SessionStore: Send + Sync + 'static {
+    async fn put_session(&self, session_id: &str, session: Session) -> Result<(), StoreError>;
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>, StoreError>;
+    /// remove and return, for rotating a session
+    async fn take_session(&self, session_id: &str) -> Result<Option<Session>, StoreError>;
+    /// true if the session was there
+    async fn delete_session(&self, session_id: &str) -> Result<bool, StoreError>;
+    /// drop sessions that expired at or before `now` (unix ms), returns how many
+    async fn purge_sessions(&self, now: u64) -> Result<usize, StoreError>;
+    /// drop every session of `user`, returns how many
+    async fn delete_user_sessions(&self, user: &str) -> Result<usize, StoreError>;
+}
+
+// a store shared behind an Arc is still a store, so the auth server and a guard::SessionLayer
+// in the same process can look at the same sessions
+#[tonic::async_trait]
+impl<T: UserStore> UserStore for Arc<T> {
+    async fn get(&self, name: &str) -> Result<Option<User>, StoreError> {
+        (**self).get(name).await
+    }
+    async fn put(&self, user: User) -> Result<(), StoreError> {
+        (**self).put(user).await
+    }
+    async fn delete(&self, name: &str) -> Result<bool, StoreError> {
+        (**self).delete(name).await
+    }
+    async fn list(&self) -> Result<Vec<User>, StoreError> {
+        (**self).list().await
+    }
+    async fn compare_and_swap(
+        &self,
+        name: &str,
+        current: Option<&User>,
+        new: Option<User>,
+    ) -> Result<bool, StoreError> {
+        (**self).compare_and_swap(name, current, new).await
+    }
+}
+
+#[tonic::async_trait]
+impl<T: ChallengeStore> ChallengeStore for Arc<T> {
+    async fn put_challenge(&self, auth_id: &str, challenge: Challenge) -> Result<(), StoreError> {
+        (**self).put_challenge(auth_id, challenge).await
+    }
+    async fn get_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        (**self).get_challenge(auth_id).await
+    }
+    async fn answer_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        (**self).answer_challenge(auth_id).await
+    }
+    async fn purge_challenges(&self, created_before: u64) -> Result<usize, StoreError> {
+        (**self).purge_challenges(created_before).await
+    }
+    async fn delete_user_challenges(&self, user: &str) -> Result<usize, StoreError> {
+        (**self).delete_user_challenges(user).await
+    }
+    async fn use_nonce(&self, nonce: &[u8], expires_at: u64) -> Result<bool, StoreError> {
+        (**self).use_nonce(nonce, expires_at).await
+    }
+    async fn purge_nonces(&self, now: u64) -> Result<usize, StoreError> {
+        (**self).purge_nonces(now).await
+    }
+}
+
+#[tonic::async_trait]
+impl<T: SessionStore> SessionStore for Arc<T> {
+    async fn put_session(&self, session_id: &str, session: Session) -> Result<(), StoreError> {
+        (**self).put_session(session_id, session).await
+    }
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        (**self).get_session(session_id).await
+    }
+    async fn take_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        (**self).take_session(session_id).await
+    }
+    async fn delete_session(&self, session_id: &str) -> Result<bool, StoreError> {
+        (**self).delete_session(session_id).await
+    }
+    async fn purge_sessions(&self, now: u64) -> Result<usize, StoreError> {
+        (**self).purge_sessions(now).await
+    }
+    async fn delete_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        (**self).delete_user_sessions(user).await
+    }
+}
+
+/// everything the server keeps, one backend for all of it
+pub trait Store: UserStore + ChallengeStore + SessionStore {}
+
+impl<S: UserStore + ChallengeStore + SessionStore> Store for S {}
@@ -0,0 +1,486 @@
+use std::{path::Path, sync::Arc};
+
+use redb::{
+    Database, ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle, WriteTransaction,
+};
+
+use super::{Challenge, ChallengeStore, Session, SessionStore, StoreError, User, UserStore};
+use crate::kdf::KdfParams;
+
+const USERS: TableDefinition<&str, &[u8]> = TableDefinition::new("users");
+const CHALLENGES: TableDefinition<&str, &[u8]> = TableDefinition::new("challenges");
+const SESSIONS: TableDefinition<&str, &[u8]> = TableDefinition::new("sessions");
+// keyed by the hex of the nonce
+const NONCES: TableDefinition<&str, &[u8]> = TableDefinition::new("nonces");
+
+/// first byte of every stored value. bump it when a layout changes and keep decoding the old one
+const FORMAT_VERSION: u8 = 1;
+
+fn backend(e: impl Into<redb::Error>) -> StoreError {
+    StoreError::Backend(e.into().to_string())
+}
+
+/// users, challenges, sessions and nonces in one redb file. every write is its own transaction,
+/// committed with fsync, so after a crash a value is either all there or not there at all
+#[derive(Debug, Clone)]
+pub struct KvStore {
+    db: Arc<Database>,
+}
+
+impl KvStore {
+    /// open (or create) the store, redb repairs it first if the last writer crashed
+    pub fn open(path: impl AsRef<Path>) -> Result<KvStore, StoreError> {
+        let db = Database::create(path).map_err(backend)?;
+        // create the tables up front so readers never see them missing
+        let tx = db.begin_write().map_err(backend)?;
+        for table in [USERS, CHALLENGES, SESSIONS, NONCES] {
+            tx.open_table(table).map_err(backend)?;
+        }
+        tx.commit().map_err(backend)?;
+        Ok(KvStore { db: Arc::new(db) })
+    }
+
+    /// redb blocks, keep it off the async workers
+    async fn run<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T, StoreError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || f(&db))
+            .await
+            .map_err(|e| StoreError::Backend(e.to_string()))?
+    }
+
+    async fn get_value<T, D>(
+        &self,
+        table: TableDefinition<'static, &'static str, &'static [u8]>,
+        key: &str,
+        decode: D,
+    ) -> Result<Option<T>, StoreError>
+    where
+        T: Send + 'static,
+        D: FnOnce(&[u8]) -> Result<T, StoreError> + Send + 'static,
+    {
+        let key = key.to_string();
+        self.run(move |db| {
+            let tx = db.begin_read().map_err(backend)?;
+            let table = tx.open_table(table).map_err(backend)?;
+            let value = table.get(key.as_str()).map_err(backend)?;
+            value.map(|value| decode(value.value())).transpose()
+        })
+        .await
+    }
+
+    async fn put_value(
+        &self,
+        table: TableDefinition<'static, &'static str, &'static [u8]>,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let key = key.to_string();
+        self.write(move |tx| {
+            let mut table = tx.open_table(table).map_err(backend)?;
+            table
+                .insert(key.as_str(), value.as_slice())
+                .map_err(backend)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn remove_value(
+        &self,
+        table: TableDefinition<'static, &'static str, &'static [u8]>,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let key = key.to_string();
+        self.write(move |tx| {
+            let mut table = tx.open_table(table).map_err(backend)?;
+            let removed = table.remove(key.as_str()).map_err(backend)?;
+            Ok(removed.map(|value| value.value().to_vec()))
+        })
+        .await
+    }
+
+    /// remove every value `matches` says yes to, in one transaction. a value that does not
+    /// decode is left alone and logged, one bad record must not stop the cleanup for good
+    async fn purge<D>(
+        &self,
+        table: TableDefinition<'static, &'static str, &'static [u8]>,
+        matches: D,
+    ) -> Result<usize, StoreError>
+    where
+        D: Fn(&[u8]) -> Result<bool, StoreError> + Send + 'static,
+    {
+        self.write(move |tx| {
+            let name = table.name().to_string();
+            let mut table = tx.open_table(table).map_err(backend)?;
+            let mut keys = Vec::new();
+            for entry in table.iter().map_err(backend)? {
+                let (key, value) = entry.map_err(backend)?;
+                match matches(value.value()) {
+                    Ok(true) => keys.push(key.value().to_string()),
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(
+                        table = name.as_str(),
+                        key = key.value(),
+                        error = %e,
+                        "skipping a value that does not decode"
+                    ),
+                }
+            }
+            for key in &keys {
+                table.remove(key.as_str()).map_err(backend)?;
+            }
+            Ok(keys.len())
+        })
+        .await
+    }
+
+    /// run f in a write transaction, commit only if it returns Ok
+    async fn write<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WriteTransaction) -> Result<T, StoreError> + Send + 'static,
+    {
+        self.run(move |db| {
+            let tx = db.begin_write().map_err(backend)?;
+            let result = f(&tx)?;
+            tx.commit().map_err(backend)?;
+            Ok(result)
+        })
+        .await
+    }
+}
+
+/// appends fields after the version byte, variable length ones with a u32 length in front
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn new() -> Encoder {
+        Encoder(vec![FORMAT_VERSION])
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Encoder {
+        self.0
+            .extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Encoder {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Encoder {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+}
+
+struct Decoder<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn new(value: &'a [u8]) -> Result<Decoder<'a>, StoreError> {
+        match value.split_first() {
+            Some((&FORMAT_VERSION, rest)) => Ok(Decoder { rest }),
+            Some((version, _)) => Err(StoreError::Corrupt(format!(
+                "unknown format version {}",
+                version
+            ))),
+            None => Err(StoreError::Corrupt("empty value".to_string())),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StoreError> {
+        if self.rest.len() < len {
+            return Err(StoreError::Corrupt("value is truncated".to_string()));
+        }
+        let (head, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, StoreError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, StoreError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, StoreError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, StoreError> {
+        String::from_utf8(self.bytes()?).map_err(|e| StoreError::Corrupt(e.to_string()))
+    }
+
+    /// every field read and nothing left over
+    fn finish(self) -> Result<(), StoreError> {
+        if !self.rest.is_empty() {
+            return Err(StoreError::Corrupt("trailing bytes".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn encode_user(user: &User) -> Vec<u8> {
+    Encoder::new()
+        .bytes(user.name.as_bytes())
+        .bytes(user.group.as_str().as_bytes())
+        .bytes(&user.y1)
+        .bytes(&user.y2)
+        .bytes(&user.salt)
+        .u32(user.kdf.m_cost_kib)
+        .u32(user.kdf.t_cost)
+        .u32(user.kdf.p_cost)
+        .0
+}
+
+fn decode_user(value: &[u8]) -> Result<User, StoreError> {
+    let mut decoder = Decoder::new(value)?;
+    let user = User {
+        name: decoder.string()?,
+        group: decoder.string()?.parse().map_err(StoreError::Corrupt)?,
+        y1: decoder.bytes()?,
+        y2: decoder.bytes()?,
+        salt: decoder.bytes()?,
+        kdf: KdfParams {
+            m_cost_kib: decoder.u32()?,
+            t_cost: decoder.u32()?,
+            p_cost: decoder.u32()?,
+        },
+    };
+    decoder.finish()?;
+    Ok(user)
+}
+
+fn encode_challenge(challenge: &Challenge) -> Vec<u8> {
+    Encoder::new()
+        .bytes(challenge.user.as_bytes())
+        .bytes(&challenge.r1)
+        .bytes(&challenge.r2)
+        .bytes(&challenge.c)
+        .u64(challenge.created_at)
+        .u32(challenge.attempts)
+        .0
+}
+
+fn decode_challenge(value: &[u8]) -> Result<Challenge, StoreError> {
+    let mut decoder = Decoder::new(value)?;
+    let challenge = Challenge {
+        user: decoder.string()?,
+        r1: decoder.bytes()?,
+        r2: decoder.bytes()?,
+        c: decoder.bytes()?,
+        created_at: decoder.u64()?,
+        attempts: decoder.u32()?,
+    };
+    decoder.finish()?;
+    Ok(challenge)
+}
+
+fn decode_nonce(value: &[u8]) -> Result<u64, StoreError> {
+    let mut decoder = Decoder::new(value)?;
+    let expires_at = decoder.u64()?;
+    decoder.finish()?;
+    Ok(expires_at)
+}
+
+fn encode_session(session: &Session) -> Vec<u8> {
+    Encoder::new()
+        .bytes(session.user.as_bytes())
+        .u64(session.expires_at)
+        .bytes(session.auth_method.as_bytes())
+        .0
+}
+
+fn decode_session(value: &[u8]) -> Result<Session, StoreError> {
+    let mut decoder = Decoder::new(value)?;
+    let session = Session {
+        user: decoder.string()?,
+        expires_at: decoder.u64()?,
+        auth_method: decoder.string()?,
+    };
+    decoder.finish()?;
+    Ok(session)
+}
+
+#[tonic::async_trait]
+impl UserStore for KvStore {
+    async fn get(&self, name: &str) -> Result<Option<User>, StoreError> {
+        self.get_value(USERS, name, decode_user).await
+    }
+
+    async fn put(&self, user: User) -> Result<(), StoreError> {
+        self.put_value(USERS, &user.name.clone(), encode_user(&user))
+            .await
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool, StoreError> {
+        Ok(self.remove_value(USERS, name).await?.is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<User>, StoreError> {
+        self.run(|db| {
+            let tx = db.begin_read().map_err(backend)?;
+            let table = tx.open_table(USERS).map_err(backend)?;
+            let mut users = Vec::with_capacity(table.len().map_err(backend)? as usize);
+            for entry in table.iter().map_err(backend)? {
+                let (_, value) = entry.map_err(backend)?;
+                users.push(decode_user(value.value())?);
+            }
+            Ok(users)
+        })
+        .await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        name: &str,
+        current: Option<&User>,
+        new: Option<User>,
+    ) -> Result<bool, StoreError> {
+        let name = name.to_string();
+        let current = current.cloned();
+        // redb has a single writer, nobody can change the user between our read and write
+        self.write(move |tx| {
+            let mut table = tx.open_table(USERS).map_err(backend)?;
+            let stored = table
+                .get(name.as_str())
+                .map_err(backend)?
+                .map(|value| decode_user(value.value()))
+                .transpose()?;
+            if stored != current {
+                return Ok(false);
+            }
+            match new {
+                Some(user) => {
+                    table
+                        .insert(name.as_str(), encode_user(&user).as_slice())
+                        .map_err(backend)?;
+                }
+                None => {
+                    table.remove(name.as_str()).map_err(backend)?;
+                }
+            }
+            Ok(true)
+        })
+        .await
+    }
+}
+
+#[tonic::async_trait]
+impl ChallengeStore for KvStore {
+    async fn put_challenge(&self, auth_id: &str, challenge: Challenge) -> Result<(), StoreError> {
+        self.put_value(CHALLENGES, auth_id, encode_challenge(&challenge))
+            .await
+    }
+
+    async fn get_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        self.get_value(CHALLENGES, auth_id, decode_challenge).await
+    }
+
+    async fn answer_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        let auth_id = auth_id.to_string();
+        self.write(move |tx| {
+            let mut table = tx.open_table(CHALLENGES).map_err(backend)?;
+            let stored = table
+                .get(auth_id.as_str())
+                .map_err(backend)?
+                .map(|value| decode_challenge(value.value()))
+                .transpose()?;
+            let Some(mut challenge) = stored else {
+                return Ok(None);
+            };
+            challenge.attempts += 1;
+            table
+                .insert(auth_id.as_str(), encode_challenge(&challenge).as_slice())
+                .map_err(backend)?;
+            Ok(Some(challenge))
+        })
+        .await
+    }
+
+    async fn purge_challenges(&self, created_before: u64) -> Result<usize, StoreError> {
+        self.purge(CHALLENGES, move |value| {
+            Ok(decode_challenge(value)?.created_at < created_before)
+        })
+        .await
+    }
+
+    async fn delete_user_challenges(&self, user: &str) -> Result<usize, StoreError> {
+        let user = user.to_string();
+        self.purge(CHALLENGES, move |value| {
+            Ok(decode_challenge(value)?.user == user)
+        })
+        .await
+    }
+
+    async fn use_nonce(&self, nonce: &[u8], expires_at: u64) -> Result<bool, StoreError> {
+        let key = hex::encode(nonce);
+        // single writer again, the check and the insert can't be split
+        self.write(move |tx| {
+            let mut table = tx.open_table(NONCES).map_err(backend)?;
+            if table.get(key.as_str()).map_err(backend)?.is_some() {
+                return Ok(false);
+            }
+            let value = Encoder::new().u64(expires_at).0;
+            table
+                .insert(key.as_str(), value.as_slice())
+                .map_err(backend)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn purge_nonces(&self, now: u64) -> Result<usize, StoreError> {
+        self.purge(NONCES, move |value| Ok(decode_nonce(value)? <= now))
+            .await
+    }
+}
+
+#[tonic::async_trait]
+impl SessionStore for KvStore {
+    async fn put_session(&self, session_id: &str, session: Session) -> Result<(), StoreError> {
+        self.put_value(SESSIONS, session_id, encode_session(&session))
+            .await
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        self.get_value(SESSIONS, session_id, decode_session).await
+    }
+
+    async fn take_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        self.remove_value(SESSIONS, session_id)
+            .await?
+            .map(|value| decode_session(&value))
+            .transpose()
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool, StoreError> {
+        Ok(self.remove_value(SESSIONS, session_id).await?.is_some())
+    }
+
+    async fn purge_sessions(&self, now: u64) -> Result<usize, StoreError> {
+        self.purge(SESSIONS, move |value| {
+            Ok(decode_session(value)?.expires_at <= now)
+        })
+        .await
+    }
+
+    async fn delete_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        let user = user.to_string();
+        self.purge(SESSIONS, move |value| {
+            Ok(decode_session(value)?.user == user)
+        })
+        .await
+    }
+}
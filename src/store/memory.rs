@@ -0,0 +1,146 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use super::{Challenge, ChallengeStore, Session, SessionStore, StoreError, User, UserStore};
+
+/// everything in HashMaps, gone on restart
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    users: Mutex<HashMap<String, User>>,
+    challenges: Mutex<HashMap<String, Challenge>>,
+    // nonce -> expires_at
+    nonces: Mutex<HashMap<Vec<u8>, u64>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        Self::default()
+    }
+}
+
+#[tonic::async_trait]
+impl UserStore for MemoryStore {
+    async fn get(&self, name: &str) -> Result<Option<User>, StoreError> {
+        Ok(self.users.lock().unwrap().get(name).cloned())
+    }
+
+    async fn put(&self, user: User) -> Result<(), StoreError> {
+        self.users.lock().unwrap().insert(user.name.clone(), user);
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool, StoreError> {
+        Ok(self.users.lock().unwrap().remove(name).is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<User>, StoreError> {
+        Ok(self.users.lock().unwrap().values().cloned().collect())
+    }
+
+    async fn compare_and_swap(
+        &self,
+        name: &str,
+        current: Option<&User>,
+        new: Option<User>,
+    ) -> Result<bool, StoreError> {
+        let users = &mut self.users.lock().unwrap();
+        if users.get(name) != current {
+            return Ok(false);
+        }
+        match new {
+            Some(user) => users.insert(name.to_string(), user),
+            None => users.remove(name),
+        };
+        Ok(true)
+    }
+}
+
+#[tonic::async_trait]
+impl ChallengeStore for MemoryStore {
+    async fn put_challenge(&self, auth_id: &str, challenge: Challenge) -> Result<(), StoreError> {
+        self.challenges
+            .lock()
+            .unwrap()
+            .insert(auth_id.to_string(), challenge);
+        Ok(())
+    }
+
+    async fn get_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        Ok(self.challenges.lock().unwrap().get(auth_id).cloned())
+    }
+
+    async fn answer_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        let challenges = &mut self.challenges.lock().unwrap();
+        Ok(challenges.get_mut(auth_id).map(|challenge| {
+            challenge.attempts += 1;
+            challenge.clone()
+        }))
+    }
+
+    async fn purge_challenges(&self, created_before: u64) -> Result<usize, StoreError> {
+        let challenges = &mut self.challenges.lock().unwrap();
+        let before = challenges.len();
+        challenges.retain(|_, challenge| challenge.created_at >= created_before);
+        Ok(before - challenges.len())
+    }
+
+    async fn delete_user_challenges(&self, user: &str) -> Result<usize, StoreError> {
+        let challenges = &mut self.challenges.lock().unwrap();
+        let before = challenges.len();
+        challenges.retain(|_, challenge| challenge.user != user);
+        Ok(before - challenges.len())
+    }
+
+    async fn use_nonce(&self, nonce: &[u8], expires_at: u64) -> Result<bool, StoreError> {
+        let nonces = &mut self.nonces.lock().unwrap();
+        if nonces.contains_key(nonce) {
+            return Ok(false);
+        }
+        nonces.insert(nonce.to_vec(), expires_at);
+        Ok(true)
+    }
+
+    async fn purge_nonces(&self, now: u64) -> Result<usize, StoreError> {
+        let nonces = &mut self.nonces.lock().unwrap();
+        let before = nonces.len();
+        nonces.retain(|_, expires_at| *expires_at > now);
+        Ok(before - nonces.len())
+    }
+}
+
+#[tonic::async_trait]
+impl SessionStore for MemoryStore {
+    async fn put_session(&self, session_id: &str, session: Session) -> Result<(), StoreError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), session);
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        Ok(self.sessions.lock().unwrap().get(session_id).cloned())
+    }
+
+    async fn take_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        Ok(self.sessions.lock().unwrap().remove(session_id))
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool, StoreError> {
+        Ok(self.sessions.lock().unwrap().remove(session_id).is_some())
+    }
+
+    async fn purge_sessions(&self, now: u64) -> Result<usize, StoreError> {
+        let sessions = &mut self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.expires_at > now);
+        Ok(before - sessions.len())
+    }
+
+    async fn delete_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        let sessions = &mut self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.user != user);
+        Ok(before - sessions.len())
+    }
+}
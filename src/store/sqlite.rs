@@ -0,0 +1,413 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+
+use super::{Challenge, ChallengeStore, Session, SessionStore, StoreError, User, UserStore};
+use crate::kdf::KdfParams;
+
+/// schema changes, in order. entry i takes the database from user_version i to i + 1,
+/// never edit one that has shipped, add a new one instead
+const MIGRATIONS: &[&str] = &[
+    // 1: users, challenges and sessions
+    "CREATE TABLE users (
+        name TEXT PRIMARY KEY,
+        grp TEXT NOT NULL,
+        y1 BLOB NOT NULL,
+        y2 BLOB NOT NULL,
+        salt BLOB NOT NULL,
+        m_cost_kib INTEGER NOT NULL,
+        t_cost INTEGER NOT NULL,
+        p_cost INTEGER NOT NULL
+    );
+    CREATE TABLE challenges (
+        auth_id TEXT PRIMARY KEY,
+        user TEXT NOT NULL,
+        r1 BLOB NOT NULL,
+        r2 BLOB NOT NULL,
+        c BLOB NOT NULL,
+        created_at INTEGER NOT NULL,
+        attempts INTEGER NOT NULL
+    );
+    CREATE INDEX challenges_created_at ON challenges (created_at);
+    CREATE TABLE sessions (
+        session_id TEXT PRIMARY KEY,
+        user TEXT NOT NULL,
+        expires_at INTEGER NOT NULL,
+        auth_method TEXT NOT NULL
+    );
+    CREATE INDEX sessions_expires_at ON sessions (expires_at);
+    CREATE INDEX sessions_user ON sessions (user);",
+    // 2: revoking everything of one user
+    "CREATE INDEX challenges_user ON challenges (user);",
+    // 3: nonces of one shot proofs, so a replay is caught across restarts
+    "CREATE TABLE nonces (
+        nonce BLOB PRIMARY KEY,
+        expires_at INTEGER NOT NULL
+    );
+    CREATE INDEX nonces_expires_at ON nonces (expires_at);",
+];
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Backend(e.to_string())
+    }
+}
+
+/// users, challenges, sessions and nonces in one SQLite file
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// open (or create) the database and bring the schema up to date
+    pub fn open(path: impl AsRef<Path>) -> Result<SqliteStore, StoreError> {
+        Self::setup(Connection::open(path)?)
+    }
+
+    /// throwaway database, mostly for tests
+    pub fn open_in_memory() -> Result<SqliteStore, StoreError> {
+        Self::setup(Connection::open_in_memory()?)
+    }
+
+    fn setup(mut conn: Connection) -> Result<SqliteStore, StoreError> {
+        // WAL survives a crash mid write and lets readers in while we write
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "FULL")?;
+        migrate(&mut conn)?;
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// schema version the database is at
+    pub fn schema_version(&self) -> Result<usize, StoreError> {
+        schema_version(&self.conn.lock().unwrap())
+    }
+
+    /// rusqlite blocks, keep it off the async workers
+    async fn run<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, StoreError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || f(&mut conn.lock().unwrap()))
+            .await
+            .map_err(|e| StoreError::Backend(e.to_string()))?
+    }
+}
+
+fn schema_version(conn: &Connection) -> Result<usize, StoreError> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// apply whatever migrations the database hasn't seen yet, each in its own transaction
+fn migrate(conn: &mut Connection) -> Result<(), StoreError> {
+    let version = schema_version(conn)?;
+    if version > MIGRATIONS.len() {
+        return Err(StoreError::Corrupt(format!(
+            "schema version {} is newer than this server knows ({})",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index as i64 + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+const USER_COLUMNS: &str = "name, grp, y1, y2, salt, m_cost_kib, t_cost, p_cost";
+
+fn user_from_row(row: &Row) -> Result<User, StoreError> {
+    let group: String = row.get(1)?;
+    Ok(User {
+        name: row.get(0)?,
+        group: group.parse().map_err(StoreError::Corrupt)?,
+        y1: row.get(2)?,
+        y2: row.get(3)?,
+        salt: row.get(4)?,
+        kdf: KdfParams {
+            m_cost_kib: row.get(5)?,
+            t_cost: row.get(6)?,
+            p_cost: row.get(7)?,
+        },
+    })
+}
+
+fn get_user(conn: &Connection, name: &str) -> Result<Option<User>, StoreError> {
+    let mut statement = conn.prepare_cached(&format!(
+        "SELECT {} FROM users WHERE name = ?1",
+        USER_COLUMNS
+    ))?;
+    let mut rows = statement.query([name])?;
+    rows.next()?.map(user_from_row).transpose()
+}
+
+fn put_user(conn: &Connection, user: &User) -> Result<(), StoreError> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO users ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            USER_COLUMNS
+        ),
+        params![
+            user.name,
+            user.group.as_str(),
+            user.y1,
+            user.y2,
+            user.salt,
+            user.kdf.m_cost_kib,
+            user.kdf.t_cost,
+            user.kdf.p_cost,
+        ],
+    )?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl UserStore for SqliteStore {
+    async fn get(&self, name: &str) -> Result<Option<User>, StoreError> {
+        let name = name.to_string();
+        self.run(move |conn| get_user(conn, &name)).await
+    }
+
+    async fn put(&self, user: User) -> Result<(), StoreError> {
+        self.run(move |conn| put_user(conn, &user)).await
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool, StoreError> {
+        let name = name.to_string();
+        self.run(move |conn| Ok(conn.execute("DELETE FROM users WHERE name = ?1", [name])? > 0))
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<User>, StoreError> {
+        self.run(|conn| {
+            let mut statement =
+                conn.prepare_cached(&format!("SELECT {} FROM users ORDER BY name", USER_COLUMNS))?;
+            let mut rows = statement.query([])?;
+            let mut users = Vec::new();
+            while let Some(row) = rows.next()? {
+                users.push(user_from_row(row)?);
+            }
+            Ok(users)
+        })
+        .await
+    }
+
+    async fn compare_and_swap(
+        &self,
+        name: &str,
+        current: Option<&User>,
+        new: Option<User>,
+    ) -> Result<bool, StoreError> {
+        let name = name.to_string();
+        let current = current.cloned();
+        self.run(move |conn| {
+            // immediate takes the write lock up front, another process can't sneak in between
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            if get_user(&tx, &name)? != current {
+                return Ok(false);
+            }
+            match new {
+                Some(user) => put_user(&tx, &user)?,
+                None => {
+                    tx.execute("DELETE FROM users WHERE name = ?1", [&name])?;
+                }
+            }
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+}
+
+const CHALLENGE_COLUMNS: &str = "user, r1, r2, c, created_at, attempts";
+
+fn challenge_from_row(row: &Row) -> Result<Challenge, StoreError> {
+    Ok(Challenge {
+        user: row.get(0)?,
+        r1: row.get(1)?,
+        r2: row.get(2)?,
+        c: row.get(3)?,
+        created_at: row.get::<_, i64>(4)? as u64,
+        attempts: row.get(5)?,
+    })
+}
+
+fn get_challenge(conn: &Connection, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+    let mut statement = conn.prepare_cached(&format!(
+        "SELECT {} FROM challenges WHERE auth_id = ?1",
+        CHALLENGE_COLUMNS
+    ))?;
+    let mut rows = statement.query([auth_id])?;
+    rows.next()?.map(challenge_from_row).transpose()
+}
+
+#[tonic::async_trait]
+impl ChallengeStore for SqliteStore {
+    async fn put_challenge(&self, auth_id: &str, challenge: Challenge) -> Result<(), StoreError> {
+        let auth_id = auth_id.to_string();
+        self.run(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO challenges (auth_id, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    CHALLENGE_COLUMNS
+                ),
+                params![
+                    auth_id,
+                    challenge.user,
+                    challenge.r1,
+                    challenge.r2,
+                    challenge.c,
+                    challenge.created_at as i64,
+                    challenge.attempts,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        let auth_id = auth_id.to_string();
+        self.run(move |conn| get_challenge(conn, &auth_id)).await
+    }
+
+    async fn answer_challenge(&self, auth_id: &str) -> Result<Option<Challenge>, StoreError> {
+        let auth_id = auth_id.to_string();
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            tx.execute(
+                "UPDATE challenges SET attempts = attempts + 1 WHERE auth_id = ?1",
+                [&auth_id],
+            )?;
+            let challenge = get_challenge(&tx, &auth_id)?;
+            tx.commit()?;
+            Ok(challenge)
+        })
+        .await
+    }
+
+    async fn purge_challenges(&self, created_before: u64) -> Result<usize, StoreError> {
+        self.run(move |conn| {
+            Ok(conn.execute(
+                "DELETE FROM challenges WHERE created_at < ?1",
+                [created_before as i64],
+            )?)
+        })
+        .await
+    }
+
+    async fn delete_user_challenges(&self, user: &str) -> Result<usize, StoreError> {
+        let user = user.to_string();
+        self.run(move |conn| Ok(conn.execute("DELETE FROM challenges WHERE user = ?1", [user])?))
+            .await
+    }
+
+    async fn use_nonce(&self, nonce: &[u8], expires_at: u64) -> Result<bool, StoreError> {
+        let nonce = nonce.to_vec();
+        self.run(move |conn| {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO nonces (nonce, expires_at) VALUES (?1, ?2)",
+                params![nonce, expires_at as i64],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn purge_nonces(&self, now: u64) -> Result<usize, StoreError> {
+        self.run(move |conn| {
+            Ok(conn.execute("DELETE FROM nonces WHERE expires_at <= ?1", [now as i64])?)
+        })
+        .await
+    }
+}
+
+fn session_from_row(row: &Row) -> Result<Session, rusqlite::Error> {
+    Ok(Session {
+        user: row.get(0)?,
+        expires_at: row.get::<_, i64>(1)? as u64,
+        auth_method: row.get(2)?,
+    })
+}
+
+#[tonic::async_trait]
+impl SessionStore for SqliteStore {
+    async fn put_session(&self, session_id: &str, session: Session) -> Result<(), StoreError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO sessions (session_id, user, expires_at, auth_method)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    session_id,
+                    session.user,
+                    session.expires_at as i64,
+                    session.auth_method,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT user, expires_at, auth_method FROM sessions WHERE session_id = ?1",
+                    [session_id],
+                    session_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    async fn take_session(&self, session_id: &str) -> Result<Option<Session>, StoreError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            Ok(conn
+                .query_row(
+                    "DELETE FROM sessions WHERE session_id = ?1
+                     RETURNING user, expires_at, auth_method",
+                    [session_id],
+                    session_from_row,
+                )
+                .optional()?)
+        })
+        .await
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<bool, StoreError> {
+        let session_id = session_id.to_string();
+        self.run(move |conn| {
+            Ok(conn.execute("DELETE FROM sessions WHERE session_id = ?1", [session_id])? > 0)
+        })
+        .await
+    }
+
+    async fn purge_sessions(&self, now: u64) -> Result<usize, StoreError> {
+        self.run(move |conn| {
+            Ok(conn.execute("DELETE FROM sessions WHERE expires_at <= ?1", [now as i64])?)
+        })
+        .await
+    }
+
+    async fn delete_user_sessions(&self, user: &str) -> Result<usize, StoreError> {
+        let user = user.to_string();
+        self.run(move |conn| Ok(conn.execute("DELETE FROM sessions WHERE user = ?1", [user])?))
+            .await
+    }
+}
@@ -0,0 +1,186 @@
+use std::{fmt, fs, io::Write, path::Path};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{
+    pkcs8::{spki::der::pem::LineEnding, DecodePrivateKey, EncodePrivateKey},
+    Signature, Signer, SigningKey, VerifyingKey,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// JWS name for Ed25519 (RFC 8037)
+pub const ALGORITHM: &str = "EdDSA";
+
+/// values for the amr claim
+pub const AMR_INTERACTIVE: &str = "zkp-interactive";
+pub const AMR_FIAT_SHAMIR: &str = "zkp-fiat-shamir";
+
+/// what a session token says, services trust it as long as the signature checks out and exp is in the future
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,      // username
+    pub iat: u64,         // issued at, unix seconds
+    pub exp: u64,         // expires at, unix seconds
+    pub amr: Vec<String>, // how the user proved who they are
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    alg: String,
+    typ: String,
+    kid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    Malformed(String),
+    UnsupportedAlgorithm(String),
+    /// kid does not match the key we check against
+    UnknownKey(String),
+    BadSignature,
+    Expired,
+    /// loading or storing the signing key
+    Key(String),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::Malformed(reason) => write!(f, "malformed token: {}", reason),
+            TokenError::UnsupportedAlgorithm(alg) => write!(f, "unsupported algorithm {}", alg),
+            TokenError::UnknownKey(kid) => write!(f, "token signed by unknown key {}", kid),
+            TokenError::BadSignature => write!(f, "bad token signature"),
+            TokenError::Expired => write!(f, "token has expired"),
+            TokenError::Key(reason) => write!(f, "signing key: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// short id for a public key (first 8 bytes of its SHA-256, hex), goes in the kid header
+pub fn key_id(public_key: &VerifyingKey) -> String {
+    hex::encode(&Sha256::digest(public_key.as_bytes())[..8])
+}
+
+/// signs session tokens as compact JWS (EdDSA JWT)
+#[derive(Debug, Clone)]
+pub struct TokenSigner {
+    key: SigningKey,
+    key_id: String,
+}
+
+impl TokenSigner {
+    pub fn new(key: SigningKey) -> TokenSigner {
+        let key_id = key_id(&key.verifying_key());
+        TokenSigner { key, key_id }
+    }
+
+    /// throwaway key, tokens die with the process
+    pub fn generate() -> TokenSigner {
+        Self::new(SigningKey::generate(&mut rand::thread_rng()))
+    }
+
+    /// PKCS#8 PEM, what `openssl genpkey -algorithm ed25519` writes
+    pub fn from_pem(pem: &str) -> Result<TokenSigner, TokenError> {
+        SigningKey::from_pkcs8_pem(pem)
+            .map(Self::new)
+            .map_err(|e| TokenError::Key(e.to_string()))
+    }
+
+    pub fn to_pem(&self) -> Result<String, TokenError> {
+        self.key
+            .to_pkcs8_pem(LineEnding::LF)
+            .map(|pem| pem.to_string())
+            .map_err(|e| TokenError::Key(e.to_string()))
+    }
+
+    /// read the key file, or create it (owner read/write only) on first start
+    pub fn load_or_generate(path: &Path) -> Result<TokenSigner, TokenError> {
+        if path.exists() {
+            let pem = fs::read_to_string(path)
+                .map_err(|e| TokenError::Key(format!("{}: {}", path.display(), e)))?;
+            return Self::from_pem(&pem);
+        }
+        let signer = Self::generate();
+        let pem = signer.to_pem()?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(pem.as_bytes()))
+            .map_err(|e| TokenError::Key(format!("{}: {}", path.display(), e)))?;
+        Ok(signer)
+    }
+
+    pub fn public_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn sign(&self, claims: &Claims) -> String {
+        let header = Header {
+            alg: ALGORITHM.to_string(),
+            typ: "JWT".to_string(),
+            kid: self.key_id.clone(),
+        };
+        let mut token = encode_json(&header);
+        token.push('.');
+        token.push_str(&encode_json(claims));
+        let signature = self.key.sign(token.as_bytes());
+        token.push('.');
+        token.push_str(&URL_SAFE_NO_PAD.encode(signature.to_bytes()));
+        token
+    }
+}
+
+fn encode_json<T: Serialize>(value: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).expect("plain structs always serialize"))
+}
+
+fn decode_json<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, TokenError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| TokenError::Malformed(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| TokenError::Malformed(e.to_string()))
+}
+
+/// check a token offline against the server's public key (GetPublicKey), `now` in unix seconds
+pub fn verify(token: &str, public_key: &VerifyingKey, now: u64) -> Result<Claims, TokenError> {
+    let mut parts = token.split('.');
+    let (header, claims, signature) = match (parts.next(), parts.next(), parts.next(), parts.next())
+    {
+        (Some(header), Some(claims), Some(signature), None) => (header, claims, signature),
+        _ => return Err(TokenError::Malformed("expected 3 parts".to_string())),
+    };
+
+    // never let the token pick the algorithm
+    let parsed: Header = decode_json(header)?;
+    if parsed.alg != ALGORITHM {
+        return Err(TokenError::UnsupportedAlgorithm(parsed.alg));
+    }
+    if parsed.kid != key_id(public_key) {
+        return Err(TokenError::UnknownKey(parsed.kid));
+    }
+
+    let signature: [u8; 64] = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| TokenError::Malformed(e.to_string()))?
+        .try_into()
+        .map_err(|_| TokenError::Malformed("signature is not 64 bytes".to_string()))?;
+    let signed = &token[..header.len() + 1 + claims.len()];
+    public_key
+        .verify_strict(signed.as_bytes(), &Signature::from_bytes(&signature))
+        .map_err(|_| TokenError::BadSignature)?;
+
+    let claims: Claims = decode_json(claims)?;
+    if now >= claims.exp {
+        return Err(TokenError::Expired);
+    }
+    Ok(claims)
+}